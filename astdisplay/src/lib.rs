@@ -4,8 +4,12 @@ extern crate proc_macro;
 use std::{collections::HashMap, fmt::Write};
 
 use proc_macro::TokenStream;
+use proc_macro2::{Delimiter, TokenTree};
 use quote::quote;
-use syn::{Attribute, Field, Fields, FieldsNamed, FieldsUnnamed, Ident, Item, Type};
+use syn::{
+    ext::IdentExt, Attribute, Field, Fields, FieldsNamed, FieldsUnnamed, GenericArgument, Ident,
+    Item, PathArguments, PathSegment, Type,
+};
 
 fn is_bool(field: &Field) -> bool {
     matches!(
@@ -14,20 +18,50 @@ fn is_bool(field: &Field) -> bool {
     )
 }
 
+// The type's own name, so std::vec::Vec<T> is a Vec like Vec<T> is.
+fn type_segment(field: &Field) -> Option<&PathSegment> {
+    match &field.ty {
+        Type::Path(type_path) => type_path.path.segments.last(),
+        _ => None,
+    }
+}
+
 fn is_vec(field: &Field) -> bool {
-    if let Type::Path(type_path) = &field.ty {
-        let segments = &type_path.path.segments;
-        return segments[0].ident == "Vec";
+    matches!(type_segment(field), Some(segment) if segment.ident == "Vec")
+}
+
+fn is_map(field: &Field) -> bool {
+    matches!(
+        type_segment(field),
+        Some(segment) if segment.ident == "HashMap" || segment.ident == "BTreeMap",
+    )
+}
+
+fn is_option_bool(field: &Field) -> bool {
+    if let Some(segment) = type_segment(field) {
+        if let (true, PathArguments::AngleBracketed(args)) =
+            (segment.ident == "Option", &segment.arguments)
+        {
+            return matches!(
+                args.args.first(),
+                Some(GenericArgument::Type(Type::Path(inner))) if inner.path.is_ident("bool"),
+            );
+        }
     }
     false
 }
 
 fn is_option(field: &Field) -> bool {
-    if let Type::Path(type_path) = &field.ty {
-        let segments = &type_path.path.segments;
-        return segments[0].ident == "Option";
-    }
-    false
+    matches!(type_segment(field), Some(segment) if segment.ident == "Option")
+}
+
+// Fields are bound under a private name so they can't shadow the generated code's own locals
+// (opts, cell, river_width and so on).
+fn binding(ident: &Ident) -> Ident {
+    Ident::new(
+        &format!("__field_{}", ident.unraw()),
+        syn::__private::Span::call_site(),
+    )
 }
 
 fn truncate_stmt_suffix(s: &str) -> &str {
@@ -35,31 +69,111 @@ fn truncate_stmt_suffix(s: &str) -> &str {
         .trim_end_matches("Statement")
 }
 
-fn split_upper<'a>(mut s: &'a str) -> Vec<&'a str> {
-    let mut indexes = Vec::new();
-    for (i, c) in s.chars().enumerate().skip(1) {
-        if c.is_uppercase() {
-            indexes.push(i);
+// Splits CamelCase and snake_case into words. A run of capitals stays one word, so SQLServer is
+// SQL Server, and a capital after a lowercase letter or digit starts a new word.
+fn split_words(s: &str) -> Vec<&str> {
+    let chars = s.char_indices().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (i, &(at, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if start < at {
+                words.push(&s[start..at]);
+            }
+            start = at + c.len_utf8();
+            continue;
+        }
+        if at == start || !c.is_uppercase() {
+            continue;
+        }
+        let prev = chars[i - 1].1;
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        if !prev.is_uppercase() || matches!(next, Some(next) if next.is_lowercase()) {
+            words.push(&s[start..at]);
+            start = at;
         }
     }
-    let mut strs = Vec::with_capacity(indexes.len());
-    let mut accum = 0;
-    for split_at in indexes {
-        let at = split_at - accum;
-        let (l, r) = s.split_at(at);
-        accum += at;
-        s = r;
-        strs.push(l);
+    if start < s.len() {
+        words.push(&s[start..]);
     }
-    strs.push(s);
-    strs
+    words
+}
+
+fn fmt_words(s: &str) -> String {
+    split_words(s).join(" ").to_uppercase()
 }
 
 fn fmt_ident(ident: &Ident) -> String {
-    split_upper(truncate_stmt_suffix(&ident.to_string()))
-        .join(" ")
-        .replace("_", " ")
-        .to_uppercase()
+    fmt_words(truncate_stmt_suffix(&ident.to_string()))
+}
+
+// How the type, variant and field names of a container become keywords.
+struct Naming {
+    // Replaces the default Statement/sStatement trimming. An empty suffix strips nothing.
+    strip_suffix: Option<String>,
+    // Applies to variant and field names; the type's own name is always UPPER SPACED.
+    rename_all: RenameAll,
+}
+
+#[derive(Clone, Copy)]
+enum RenameAll {
+    UpperSpaced,
+    LowerSpaced,
+    Verbatim,
+    Snake,
+}
+
+impl Naming {
+    fn new(attrs: &mut Attrs) -> Self {
+        let rename_all = match attrs.remove("rename_all").as_deref() {
+            None | Some("UPPER SPACED") => RenameAll::UpperSpaced,
+            Some("lower spaced") => RenameAll::LowerSpaced,
+            Some("verbatim") => RenameAll::Verbatim,
+            Some("snake") => RenameAll::Snake,
+            Some(rename_all) => panic!("unknown rename_all: {rename_all}"),
+        };
+        Self {
+            strip_suffix: attrs.remove("strip_suffix"),
+            rename_all,
+        }
+    }
+
+    fn strip<'a>(&self, ident: &'a str) -> &'a str {
+        match &self.strip_suffix {
+            Some(suffix) => match ident.strip_suffix(suffix.as_str()) {
+                Some(stripped) if !stripped.is_empty() => stripped,
+                _ => ident,
+            },
+            None => truncate_stmt_suffix(ident),
+        }
+    }
+
+    // The type's name as a keyword doc.
+    fn type_keyword(&self, ident: &Ident) -> TokenStream2 {
+        let name = fmt_words(self.strip(&ident.to_string()));
+        quote! { opts.keyword(#name) }
+    }
+
+    // A variant or field name as a doc. Only the default spelling is a keyword; the others were
+    // asked for explicitly, so keyword case doesn't touch them.
+    fn keyword(&self, ident: &Ident) -> TokenStream2 {
+        let name = self.fmt(ident);
+        match self.rename_all {
+            RenameAll::UpperSpaced => quote! { opts.keyword(#name) },
+            _ => quote! { pretty::RcDoc::text(#name) },
+        }
+    }
+
+    fn fmt(&self, ident: &Ident) -> String {
+        let ident = ident.to_string();
+        let ident = self.strip(&ident);
+        match self.rename_all {
+            RenameAll::UpperSpaced => fmt_words(ident),
+            RenameAll::LowerSpaced => split_words(ident).join(" ").to_lowercase(),
+            RenameAll::Verbatim => ident.to_string(),
+            RenameAll::Snake => split_words(ident).join("_").to_lowercase(),
+        }
+    }
 }
 
 #[proc_macro_derive(AstDisplay)]
@@ -104,14 +218,14 @@ impl AstDisplay for {} {{
                     writeln!(s, "f.write_str(\" \");").unwrap();
                 }
                 let ident = field.ident.as_ref().unwrap();
-                if is_bool(&field) {
+                if is_bool(field) {
                     writeln!(
                         s,
                         "f.write_str(\"{}\");",
                         ident.to_string().to_uppercase().replace("_", " ")
                     )
                     .unwrap();
-                } else if is_vec(&field) {
+                } else if is_vec(field) {
                     writeln!(
                         s,
                         "f.write_node(&mz_sql_parser::ast::display::comma_separated(&self.{}));",
@@ -141,51 +255,200 @@ impl Attrs {
             }
             for tok in attr.tokens.clone().into_iter() {
                 // TODO: Surely there's a better way to do this.
-                if let quote::__private::TokenTree::Group(group) = tok {
-                    let mut toks = group
-                        .stream()
-                        .into_iter()
-                        .map(|tok| tok.to_string())
-                        .peekable();
-                    while toks.peek().is_some() {
-                        let name = toks.next().unwrap();
-                        let mut value = "".to_string();
-                        if let Some("=") = toks.peek().cloned().as_deref() {
-                            toks.next();
-                            value = toks.next().unwrap();
-                            // Trim off the quotes. Gotta be a better way?
-                            value = value[1..value.len() - 1].to_string();
-                        }
-                        map.insert(name, value);
-                        match toks.next().as_deref() {
-                            Some(",") => continue,
-                            None => break,
-                            _ => panic!("unexpected attribute token"),
-                        }
-                    }
+                if let TokenTree::Group(group) = tok {
+                    Self::parse(group.stream(), "", &mut map);
                 }
             }
         }
         Self(map)
     }
 
+    // Parses `a, b = "x", c(d = "y")` into a, b and c.d. Keys are prefixed with prefix.
+    fn parse(stream: TokenStream2, prefix: &str, map: &mut HashMap<String, String>) {
+        let mut toks = stream.into_iter().peekable();
+        while let Some(name) = toks.next() {
+            let name = format!("{prefix}{name}");
+            let mut value = "".to_string();
+            match toks.peek() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {
+                    toks.next();
+                    value = toks
+                        .next()
+                        .unwrap_or_else(|| panic!("missing value for {name}"))
+                        .to_string();
+                    // Trim off the quotes. Gotta be a better way?
+                    if value.starts_with('"') {
+                        value = value[1..value.len() - 1].to_string();
+                    }
+                }
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                    let stream = group.stream();
+                    toks.next();
+                    Self::parse(stream, &format!("{name}."), map);
+                    // The nested keys are the attribute; there's no plain one.
+                    match toks.next() {
+                        Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => continue,
+                        None => break,
+                        _ => panic!("unexpected attribute token"),
+                    }
+                }
+                _ => {}
+            }
+            map.insert(name, value);
+            match toks.next() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => continue,
+                None => break,
+                _ => panic!("unexpected attribute token"),
+            }
+        }
+    }
+
     fn remove(&mut self, key: &str) -> Option<String> {
         self.0.remove(key)
     }
 
-    fn rename(&mut self, name: &str) -> String {
-        self.remove("rename").unwrap_or_else(|| name.to_string())
+    fn has(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    // name is the doc of the derived name.
+    fn rename(&mut self, name: &TokenStream2) -> TokenStream2 {
+        let renamed = self
+            .0
+            .keys()
+            .any(|k| k == "rename" || k.starts_with("rename."));
+        if renamed && (self.has("singular") || self.has("plural")) {
+            panic!("rename and singular/plural are exclusive");
+        }
+        let name = match self.remove("rename") {
+            Some(rename) => quote! { opts.keyword(#rename) },
+            None => name.clone(),
+        };
+        self.dialects(
+            "rename",
+            name,
+            |spelling| quote! { opts.keyword(#spelling) },
+        )
     }
 
-    fn name(&mut self, mut doc: TokenStream2, name: &str) -> TokenStream2 {
+    // Picks the singular keyword when count (a list) has one item and the plural otherwise.
+    fn number(&mut self, name: TokenStream2, count: &TokenStream2) -> TokenStream2 {
+        match (self.remove("singular"), self.remove("plural")) {
+            (None, None) => name,
+            (Some(singular), Some(plural)) => quote! {
+                if #count.len() == 1 {
+                    opts.keyword(#singular)
+                } else {
+                    opts.keyword(#plural)
+                }
+            },
+            _ => panic!("singular and plural must be given together"),
+        }
+    }
+
+    // Containers name the list field to count with count = "field".
+    fn container_number(&mut self, name: TokenStream2) -> TokenStream2 {
+        match self.remove("count") {
+            Some(count) => {
+                let count = binding(&Ident::new(&count, syn::__private::Span::call_site()));
+                self.number(name, &quote! { #count })
+            }
+            None if self.has("singular") || self.has("plural") => {
+                panic!("singular and plural on a struct or variant need count = \"field\"")
+            }
+            None => name,
+        }
+    }
+
+    // Picks the key(dialect = "...") spelling for opts.dialect at runtime, falling back to
+    // default. The dialect keys are the snake_case names of the Dialect variants. spell turns a
+    // spelling into the same kind of expression as default.
+    fn dialects(
+        &mut self,
+        key: &str,
+        default: TokenStream2,
+        spell: impl Fn(String) -> TokenStream2,
+    ) -> TokenStream2 {
+        let prefix = format!("{key}.");
+        let mut keys = self
+            .0
+            .keys()
+            .filter(|k| k.starts_with(&prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return default;
+        }
+        keys.sort();
+        let arms = keys.into_iter().map(|k| {
+            let spelling = spell(self.remove(&k).unwrap());
+            let name = &k[prefix.len()..];
+            let dialect = name
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                        None => panic!("dialect names are snake_case words: {key}({name} = ..)"),
+                    }
+                })
+                .collect::<String>();
+            let dialect = syn::parse_str::<Ident>(&dialect).unwrap_or_else(|_| {
+                panic!("dialect names are snake_case words: {key}({name} = ..)")
+            });
+            quote! { Dialect::#dialect => #spelling, }
+        });
+        quote! { {
+            #[allow(unreachable_patterns)]
+            let spelling = match opts.dialect {
+                #(#arms)*
+                _ => #default,
+            };
+            spelling
+        } }
+    }
+
+    // A keyword attribute like nest or else, with its per-dialect spellings.
+    fn spelling(&mut self, key: &str) -> Option<TokenStream2> {
+        match self.remove(key) {
+            Some(spelling) => Some(self.dialects(key, quote! { #spelling }, |spelling| {
+                quote! { #spelling }
+            })),
+            None => {
+                let prefix = format!("{key}.");
+                if self.0.keys().any(|k| k.starts_with(&prefix)) {
+                    panic!("{key}(..) needs a default {key} = \"...\" as well");
+                }
+                None
+            }
+        }
+    }
+
+    fn indent(&mut self, default: &Indent) -> Indent {
+        let align = self.remove("align").is_some();
+        match (self.remove("nest_width"), align) {
+            (Some(_), true) => panic!("nest_width and align are exclusive"),
+            (Some(width), false) => {
+                let width: isize = width
+                    .parse()
+                    .unwrap_or_else(|_| panic!("nest_width must be a number: {width}"));
+                Indent::Nest(quote! { #width })
+            }
+            (None, true) => Indent::Align,
+            (None, false) => default.clone(),
+        }
+    }
+
+    fn name(
+        &mut self,
+        mut doc: TokenStream2,
+        name: &TokenStream2,
+        indent: &Indent,
+    ) -> TokenStream2 {
         let name = self.rename(name);
         if self.remove("no_name").is_none() {
-            doc = quote! { #doc.map(|doc|
-                pretty::RcDoc::text(#name)
-                .append(pretty::RcDoc::line())
-                .append(doc)
-                .nest(#NEST)
-            ) };
+            let doc_ = indent.hang(name, quote! { doc });
+            doc = quote! { #doc.map(|doc| #doc_) };
         }
         doc
     }
@@ -204,19 +467,14 @@ impl Attrs {
         doc
     }
 
-    fn nest(&mut self, mut doc: TokenStream2) -> TokenStream2 {
-        if let Some(nest) = self.remove("nest") {
-            doc = quote! { #doc.map(|doc|
-                pretty::RcDoc::text(#nest)
-                .append(pretty::RcDoc::line())
-                .append(doc)
-                .nest(#NEST)
-                .group()
-            ) };
+    fn nest(&mut self, mut doc: TokenStream2, indent: &Indent) -> TokenStream2 {
+        if let Some(nest) = self.spelling("nest") {
+            let doc_ = indent.hang(quote! { opts.keyword(#nest) }, quote! { doc });
+            doc = quote! { #doc.map(|doc| #doc_.group()) };
             if let Some(suffix) = self.remove("nest_suffix") {
                 doc = quote! { #doc.map(|doc| doc
                     .append(pretty::RcDoc::line())
-                    .append(pretty::RcDoc::text(#suffix))
+                    .append(opts.keyword(#suffix))
                     .group()
                 ) };
             }
@@ -224,9 +482,65 @@ impl Attrs {
         doc
     }
 
+    fn delimiters(&mut self, mut doc: TokenStream2, indent: &Indent) -> TokenStream2 {
+        if let Some(delimiters) = self.remove("delimiters") {
+            let (open, close) = match delimiters.as_str() {
+                "()" => ("(", ")"),
+                "[]" => ("[", "]"),
+                "{}" => ("{", "}"),
+                _ => panic!("unsupported delimiters: {delimiters}"),
+            };
+            let doc_ = indent.delimit(open, close, quote! { doc });
+            doc = quote! { #doc.map(|doc| #doc_) };
+        }
+        doc
+    }
+
+    // Parentheses around any field's doc, grouped so the body breaks onto its own nested lines.
+    fn parens(&mut self, mut doc: TokenStream2, indent: &Indent) -> TokenStream2 {
+        if self.remove("parens").is_some() {
+            let doc_ = indent.delimit("(", ")", quote! { doc });
+            doc = quote! { #doc.map(|doc| #doc_.group()) };
+        }
+        doc
+    }
+
+    // NAME( + args + ). The args are whatever docs the fields made, possibly none.
+    fn call(&mut self, mut doc: TokenStream2, unit: bool, indent: &Indent) -> TokenStream2 {
+        if let Some(call) = self.remove("call") {
+            // The call replaces the name, so no_name has nothing left to drop.
+            self.remove("no_name");
+            let args = if unit {
+                quote! { pretty::RcDoc::nil() }
+            } else {
+                quote! { #doc.unwrap_or_else(pretty::RcDoc::nil) }
+            };
+            let args = indent.delimit("(", ")", args);
+            doc = quote! { Some(opts.keyword(#call).append(#args).group()) };
+        }
+        doc
+    }
+
+    // Missing fields (false, None, empty) show the else text instead, or nil with show_empty.
     fn els(&mut self, mut doc: TokenStream2) -> TokenStream2 {
-        if let Some(els) = self.remove("else") {
-            doc = quote! { Some(#doc.unwrap_or_else(|| pretty::RcDoc::text(#els))) };
+        if self.has("else") && self.has("show_empty") {
+            panic!("else and show_empty are exclusive");
+        }
+        if let Some(els) = self.spelling("else") {
+            doc = quote! { Some(#doc.unwrap_or_else(|| opts.keyword(#els))) };
+        }
+        doc
+    }
+
+    // ident is a reference to the field's value.
+    fn skip(&mut self, mut doc: TokenStream2, ident: &Ident, ty: &Type) -> TokenStream2 {
+        if let Some(skip_if) = self.remove("skip_if") {
+            let skip_if: syn::Path = syn::parse_str(&skip_if)
+                .unwrap_or_else(|_| panic!("skip_if must be a path: {skip_if}"));
+            doc = quote! { if #skip_if(#ident) { None } else { #doc } };
+        }
+        if self.remove("skip_default").is_some() {
+            doc = quote! { if *#ident == <#ty as Default>::default() { None } else { #doc } };
         }
         doc
     }
@@ -238,6 +552,162 @@ impl Attrs {
         doc
     }
 
+    // Map entries are key = value.
+    fn list(&mut self, ident: &Ident, map: bool) -> TokenStream2 {
+        let docs = match self.remove("align_cell") {
+            Some(_) if map => panic!("unsupported: align_cell on a map"),
+            // Left cells are measured laid out flat, and padded only when the list breaks. A left
+            // cell that breaks fails the padded layout, so the union falls back to the plain one.
+            Some(cell) => quote! { {
+                let cells = #ident
+                    .iter()
+                    .map(|v| {
+                        let (left, right) = v.to_doc_cells(opts, #cell);
+                        let width = right.as_ref().and_then(|_| opts.width(&left));
+                        (left, width, right)
+                    })
+                    .collect::<Vec<_>>();
+                let max = cells.iter().filter_map(|(_, width, _)| *width).max().unwrap_or(0);
+                cells.into_iter().map(move |(left, width, right)| match (width, right) {
+                    (Some(width), Some(right)) => left
+                        .clone()
+                        .append(pretty::RcDoc::fail().flat_alt(pretty::RcDoc::nil()))
+                        .group()
+                        .append(opts.padding(max - width).flat_alt(pretty::RcDoc::nil()))
+                        .append(pretty::RcDoc::text(" "))
+                        .append(right.clone())
+                        .union(left.append(pretty::RcDoc::text(" ")).append(right)),
+                    (None, Some(right)) => left.append(pretty::RcDoc::text(" ")).append(right),
+                    (_, None) => left,
+                })
+            } },
+            // Collecting into a BTreeMap puts a HashMap's entries in key order too.
+            None if map => quote! {
+                #ident
+                    .iter()
+                    .collect::<std::collections::BTreeMap<_, _>>()
+                    .into_iter()
+                    .map(|(k, v)| {
+                        k.to_doc_with(opts)
+                            .append(pretty::RcDoc::text(" = "))
+                            .append(v.to_doc_with(opts))
+                    })
+            },
+            None => quote! { #ident.iter().map(|v| v.to_doc_with(opts)) },
+        };
+        let docs = match self.remove("item_prefix") {
+            Some(prefix) => quote! { #docs.map(|doc| pretty::RcDoc::text(#prefix).append(doc)) },
+            None => docs,
+        };
+        let docs = match self.remove("item_suffix") {
+            Some(suffix) => quote! { #docs.map(|doc| doc.append(pretty::RcDoc::text(#suffix))) },
+            None => docs,
+        };
+        let keyword_separator = self.remove("keyword_separator");
+        if keyword_separator.is_some() && self.has("separator") {
+            panic!("separator and keyword_separator are exclusive");
+        }
+        let sep = self.remove("separator").unwrap_or_else(|| ",".to_string());
+        let noline = self.remove("separator_noline").is_some();
+        // Filled lists break each line on its own instead of all at once.
+        let (line, line_) = if self.remove("fill").is_some() {
+            (
+                quote! { pretty::RcDoc::softline() },
+                quote! { pretty::RcDoc::softline_() },
+            )
+        } else {
+            (
+                quote! { pretty::RcDoc::line() },
+                quote! { pretty::RcDoc::line_() },
+            )
+        };
+        // A keyword separator sits on a line of its own when the list breaks.
+        if let Some(keyword) = keyword_separator {
+            self.remove("list_style");
+            return quote! {
+                pretty::RcDoc::intersperse(
+                    #docs,
+                    #line.append(opts.keyword(#keyword)).append(#line)
+                )
+            };
+        }
+        let line = if noline {
+            quote! { pretty::RcDoc::nil() }
+        } else {
+            line
+        };
+        let trailing = quote! {
+            pretty::RcDoc::intersperse(#docs, pretty::RcDoc::text(#sep).append(#line))
+        };
+        // Lists that never break or have nothing to move around all look the same.
+        if sep.is_empty() || noline {
+            self.remove("list_style");
+            return trailing;
+        }
+        let leading = quote! {
+            pretty::RcDoc::intersperse(
+                #docs,
+                #line_
+                    .append(pretty::RcDoc::text(#sep))
+                    .append(pretty::RcDoc::text(" "))
+            )
+        };
+        let trailing_when_broken = quote! {
+            #trailing.append(pretty::RcDoc::text(#sep).flat_alt(pretty::RcDoc::nil()))
+        };
+        match self.remove("list_style").as_deref() {
+            Some("trailing") => trailing,
+            Some("leading") => leading,
+            Some("trailing_when_broken") => trailing_when_broken,
+            Some(style) => panic!("unknown list_style: {style}"),
+            None => quote! {
+                match opts.list_style {
+                    ListStyle::Trailing => #trailing,
+                    ListStyle::Leading => #leading,
+                    ListStyle::TrailingWhenBroken => #trailing_when_broken,
+                }
+            },
+        }
+    }
+
+    fn break_before(&mut self, separator: &TokenStream2) -> TokenStream2 {
+        match self.remove("break_before").as_deref() {
+            None => separator.clone(),
+            Some("line") => quote! { pretty::RcDoc::line() },
+            Some("softline") => quote! { pretty::RcDoc::line_() },
+            Some("hardline") => quote! { pretty::RcDoc::hardline() },
+            Some("glue") => quote! { pretty::RcDoc::nil() },
+            Some(kind) => panic!("unknown break_before: {kind}"),
+        }
+    }
+
+    fn grouping(&mut self) -> Grouping {
+        let never_break = self.remove("never_break").is_some();
+        let always_break = self.remove("always_break").is_some();
+        let no_group = self.remove("no_group").is_some();
+        match (never_break, always_break, no_group) {
+            (false, false, false) => Grouping::Group,
+            (true, false, false) => Grouping::NeverBreak,
+            (false, true, false) => Grouping::AlwaysBreak,
+            (false, false, true) => Grouping::NoGroup,
+            _ => panic!("never_break, always_break and no_group are exclusive"),
+        }
+    }
+
+    fn max_flat_items(&mut self, ident: &Ident, grouping: Grouping) -> TokenStream2 {
+        let group = grouping.apply(quote! { doc });
+        match self.remove("max_flat_items") {
+            Some(max) => {
+                let max: usize = max
+                    .parse()
+                    .unwrap_or_else(|_| panic!("max_flat_items must be a number: {max}"));
+                let always = Grouping::AlwaysBreak.apply(quote! { doc });
+                quote! { |doc| if #ident.len() > #max { #always } else { #group } }
+            }
+            None => quote! { |doc| #group },
+        }
+    }
+
     fn separator(&mut self, default: &str) -> TokenStream2 {
         let sep = self
             .remove("separator")
@@ -252,66 +722,396 @@ impl Attrs {
 
 impl Drop for Attrs {
     fn drop(&mut self) {
-        if !self.0.is_empty() {
+        // Leave an earlier panic's message to be reported rather than aborting on a second one.
+        if !self.0.is_empty() && !std::thread::panicking() {
             panic!("unknown attributes: {:?}", self.0.keys());
         }
     }
 }
 
-const NEST: isize = 4;
+#[derive(Clone)]
+enum Indent {
+    /// Nest by a fixed width from the enclosing indentation.
+    Nest(TokenStream2),
+    /// Indent to the column the doc starts at.
+    Align,
+}
+
+impl Indent {
+    // doc is a pretty::RcDoc, not an Option.
+    fn apply(&self, doc: TokenStream2) -> TokenStream2 {
+        match self {
+            Indent::Nest(width) => quote! { #doc.nest(#width) },
+            Indent::Align => quote! { opts.align(#doc) },
+        }
+    }
+
+    // open + doc + close, with the doc on its own nested lines when broken.
+    fn delimit(&self, open: &str, close: &str, doc: TokenStream2) -> TokenStream2 {
+        match self {
+            Indent::Nest(width) => quote! {
+                pretty::RcDoc::text(#open)
+                    .append(pretty::RcDoc::line_().append(#doc).nest(#width))
+                    .append(pretty::RcDoc::line_())
+                    .append(pretty::RcDoc::text(#close))
+            },
+            // Aligned values start right after the open delimiter.
+            Indent::Align => {
+                let doc = self.apply(doc);
+                quote! {
+                    pretty::RcDoc::text(#open)
+                        .append(#doc)
+                        .append(pretty::RcDoc::text(#close))
+                }
+            }
+        }
+    }
+
+    // Appends doc to head, either nested on the next line or aligned after it on the same line.
+    fn hang(&self, head: TokenStream2, doc: TokenStream2) -> TokenStream2 {
+        match self {
+            Indent::Nest(_) => self.apply(quote! {
+                (#head).append(pretty::RcDoc::line()).append(#doc)
+            }),
+            Indent::Align => {
+                let doc = self.apply(doc);
+                quote! { (#head).append(pretty::RcDoc::text(" ")).append(#doc) }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Grouping {
+    Group,
+    NoGroup,
+    NeverBreak,
+    AlwaysBreak,
+}
+
+impl Grouping {
+    // doc is a pretty::RcDoc, not an Option.
+    fn apply(self, doc: TokenStream2) -> TokenStream2 {
+        match self {
+            Grouping::Group => quote! { #doc.group() },
+            Grouping::NoGroup => doc,
+            Grouping::NeverBreak => quote! { opts.flat(#doc) },
+            // A group whose flat layout fails can never fit, so it always breaks.
+            Grouping::AlwaysBreak => quote! {
+                #doc.append(pretty::RcDoc::nil().flat_alt(pretty::RcDoc::fail())).group()
+            },
+        }
+    }
+}
 
 #[proc_macro_derive(ToDoc, attributes(todoc))]
 pub fn derive_to_doc(item: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(item as Item);
     match input {
         Item::Enum(item) => {
-            let _enum_attrs = Attrs::new(&item.attrs);
-            let variants = item.variants.iter().map(|variant| {
+            let mut enum_attrs = Attrs::new(&item.attrs);
+            let enum_indent = enum_attrs.indent(&Indent::Nest(quote! { opts.indent as isize }));
+            let naming = Naming::new(&mut enum_attrs);
+            let enum_transparent = enum_attrs.remove("transparent").is_some();
+            let mut variants = Vec::new();
+            let mut cells = Vec::new();
+            let mut field_docs = Vec::new();
+            let mut river_widths = Vec::new();
+            for variant in &item.variants {
                 let ident = &variant.ident;
                 let mut variant_attrs = Attrs::new(&variant.attrs);
-                let name = variant_attrs
-                    .remove("rename")
-                    .unwrap_or_else(|| fmt_ident(&variant.ident));
-                let FromFields { fields, doc } =
-                    from_fields(&variant.fields, &name, variant_attrs.separator(""));
+                if variant_attrs.remove("transparent").is_some() || enum_transparent {
+                    let Transparent {
+                        pattern,
+                        doc,
+                        inner,
+                    } = transparent(&variant.fields, &enum_indent);
+                    variants.push(quote! { Self::#ident #pattern => #doc, });
+                    if let Some(inner) = inner {
+                        cells.push(
+                            quote! { Self::#ident #pattern => #inner.to_doc_cells(opts, cell), },
+                        );
+                        field_docs.push(quote! {
+                            Self::#ident #pattern => #inner.to_doc_fields(opts, river_width),
+                        });
+                        river_widths
+                            .push(quote! { Self::#ident #pattern => #inner.river_width(opts), });
+                    }
+                    continue;
+                }
+                let name = naming.keyword(&variant.ident);
+                let name = variant_attrs.rename(&name);
+                let name = variant_attrs.container_number(name);
+                let grouping = variant_attrs.grouping();
+                let indent = variant_attrs.indent(&enum_indent);
+                let called = variant_attrs.has("call");
+                let wrapped = ["ignore", "call", "prefix", "suffix", "nest"]
+                    .iter()
+                    .any(|key| variant_attrs.has(key));
+                let FromFields {
+                    fields,
+                    doc,
+                    cells: variant_cells,
+                    field_docs: variant_field_docs,
+                    river_width: variant_river_width,
+                } = from_fields(
+                    &variant.fields,
+                    &name,
+                    &naming,
+                    variant_attrs.separator(""),
+                    grouping,
+                    &indent,
+                    called,
+                );
                 let doc = if variant_attrs.remove("ignore").is_some() {
                     quote! { None }
                 } else {
+                    let unit = matches!(variant.fields, Fields::Unit);
+                    let doc = variant_attrs.call(doc, unit, &indent);
                     let doc = variant_attrs.prefix(doc);
                     let doc = variant_attrs.suffix(doc);
-                    let doc = variant_attrs.nest(doc);
-                    doc
+                    variant_attrs.nest(doc, &indent)
                 };
-                quote! { Self::#ident #fields => #doc.unwrap_or_else(pretty::RcDoc::nil), }
-            });
+                match variant_cells {
+                    Some(variant_cells) if !wrapped => {
+                        cells.push(quote! { Self::#ident #fields => #variant_cells, })
+                    }
+                    _ => {}
+                }
+                match variant_field_docs {
+                    Some(variant_field_docs) if !wrapped => {
+                        field_docs.push(quote! { Self::#ident #fields => #variant_field_docs, })
+                    }
+                    _ => {}
+                }
+                match variant_river_width {
+                    Some(variant_river_width) if !wrapped => {
+                        river_widths.push(quote! { Self::#ident #fields => #variant_river_width, })
+                    }
+                    _ => {}
+                }
+                variants.push(
+                    quote! { Self::#ident #fields => #doc.unwrap_or_else(pretty::RcDoc::nil), },
+                );
+            }
+            let cells = if cells.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    fn to_doc_cells(
+                        &self,
+                        opts: &FormatOptions,
+                        cell: &str,
+                    ) -> (pretty::RcDoc<()>, Option<pretty::RcDoc<()>>) {
+                        #[allow(unreachable_patterns)]
+                        match self {
+                            #(#cells)*
+                            _ => (self.to_doc_with(opts), None),
+                        }
+                    }
+                }
+            };
+            let field_docs = if field_docs.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    #[allow(unused_variables)]
+                    fn to_doc_fields(
+                        &self,
+                        opts: &FormatOptions,
+                        river_width: Option<usize>,
+                    ) -> Vec<(pretty::RcDoc<()>, pretty::RcDoc<()>)> {
+                        #[allow(unreachable_patterns)]
+                        match self {
+                            #(#field_docs)*
+                            _ => vec![(self.to_doc_with(opts), pretty::RcDoc::line())],
+                        }
+                    }
+                }
+            };
+            let river_widths = if river_widths.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    fn river_width(&self, opts: &FormatOptions) -> Option<usize> {
+                        #[allow(unreachable_patterns)]
+                        match self {
+                            #(#river_widths)*
+                            _ => None,
+                        }
+                    }
+                }
+            };
             let item_ident = item.ident;
             let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
             quote! {
                 impl #impl_generics ToDoc for #item_ident #ty_generics #where_clause {
-                    fn to_doc(&self) -> pretty::RcDoc<()> {
+                    fn to_doc_with(&self, opts: &FormatOptions) -> pretty::RcDoc<()> {
                         match self {
                             #(#variants)*
                         }
                     }
+
+                    #cells
+
+                    #field_docs
+
+                    #river_widths
                 }
             }
             .into()
         }
         Item::Struct(item) => {
             let mut struct_attrs = Attrs::new(&item.attrs);
-            let name = fmt_ident(&item.ident);
-            let FromFields { fields, doc } =
-                from_fields(&item.fields, &name, struct_attrs.separator(""));
             let item_ident = item.ident;
             let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
-            let doc = struct_attrs.name(doc, &name);
+            if struct_attrs.remove("transparent").is_some() {
+                let Transparent {
+                    pattern,
+                    doc,
+                    inner,
+                } = transparent(&item.fields, &Indent::Nest(quote! { opts.indent as isize }));
+                let delegated = match inner {
+                    Some(inner) => quote! {
+                        fn to_doc_cells(
+                            &self,
+                            opts: &FormatOptions,
+                            cell: &str,
+                        ) -> (pretty::RcDoc<()>, Option<pretty::RcDoc<()>>) {
+                            let Self #pattern = self;
+                            #inner.to_doc_cells(opts, cell)
+                        }
+
+                        fn to_doc_fields(
+                            &self,
+                            opts: &FormatOptions,
+                            river_width: Option<usize>,
+                        ) -> Vec<(pretty::RcDoc<()>, pretty::RcDoc<()>)> {
+                            let Self #pattern = self;
+                            #inner.to_doc_fields(opts, river_width)
+                        }
+
+                        fn river_width(&self, opts: &FormatOptions) -> Option<usize> {
+                            let Self #pattern = self;
+                            #inner.river_width(opts)
+                        }
+                    },
+                    None => quote! {},
+                };
+                return quote! {
+                    impl #impl_generics ToDoc for #item_ident #ty_generics #where_clause {
+                        fn to_doc_with(&self, opts: &FormatOptions) -> pretty::RcDoc<()> {
+                            let Self #pattern = self;
+                            #doc
+                        }
+
+                        #delegated
+                    }
+                }
+                .into();
+            }
+            let naming = Naming::new(&mut struct_attrs);
+            let grouping = struct_attrs.grouping();
+            let indent = struct_attrs.indent(&Indent::Nest(quote! { opts.indent as isize }));
+            if let Some(template) = struct_attrs.remove("template") {
+                // A template spells out its own words, so there is no name to number.
+                if ["count", "singular", "plural"]
+                    .iter()
+                    .any(|key| struct_attrs.has(key))
+                {
+                    panic!("unsupported: count, singular or plural with a template");
+                }
+                let (fields, doc) =
+                    template_fields(&item.fields, &template, &naming, grouping, &indent);
+                return quote! {
+                    impl #impl_generics ToDoc for #item_ident #ty_generics #where_clause {
+                        fn to_doc_with(&self, opts: &FormatOptions) -> pretty::RcDoc<()> {
+                            let Self #fields = self;
+                            #doc
+                        }
+                    }
+                }
+                .into();
+            }
+            let name = naming.type_keyword(&item_ident);
+            let name = struct_attrs.container_number(name);
+            let called = struct_attrs.has("call");
+            let wrapped = !struct_attrs.has("no_name") || struct_attrs.has("suffix") || called;
+            let FromFields {
+                fields,
+                doc,
+                cells,
+                field_docs,
+                river_width,
+            } = from_fields(
+                &item.fields,
+                &name,
+                &naming,
+                struct_attrs.separator(""),
+                grouping,
+                &indent,
+                called,
+            );
+            let unit = matches!(item.fields, Fields::Unit);
+            let doc = if called {
+                struct_attrs.call(doc, unit, &indent)
+            } else {
+                struct_attrs.name(doc, &name, &indent)
+            };
             let doc = struct_attrs.suffix(doc);
+            let doc = match grouping {
+                Grouping::AlwaysBreak => Grouping::Group,
+                grouping => grouping,
+            }
+            .apply(quote! { #doc.unwrap_or_else(|| #name) });
+            let cells = match cells {
+                Some(cells) if !wrapped => quote! {
+                    fn to_doc_cells(
+                        &self,
+                        opts: &FormatOptions,
+                        cell: &str,
+                    ) -> (pretty::RcDoc<()>, Option<pretty::RcDoc<()>>) {
+                        let Self #fields = self;
+                        #cells
+                    }
+                },
+                _ => quote! {},
+            };
+            let field_docs = match field_docs {
+                Some(field_docs) if !wrapped => quote! {
+                    #[allow(unused_variables)]
+                    fn to_doc_fields(
+                        &self,
+                        opts: &FormatOptions,
+                        river_width: Option<usize>,
+                    ) -> Vec<(pretty::RcDoc<()>, pretty::RcDoc<()>)> {
+                        let Self #fields = self;
+                        #field_docs
+                    }
+                },
+                _ => quote! {},
+            };
+            let river_width = match river_width {
+                Some(river_width) if !wrapped => quote! {
+                    fn river_width(&self, opts: &FormatOptions) -> Option<usize> {
+                        let Self #fields = self;
+                        #river_width
+                    }
+                },
+                _ => quote! {},
+            };
             quote! {
                 impl #impl_generics ToDoc for #item_ident #ty_generics #where_clause {
-                    fn to_doc(&self) -> pretty::RcDoc<()> {
+                    fn to_doc_with(&self, opts: &FormatOptions) -> pretty::RcDoc<()> {
                         let Self #fields = self;
-                        #doc.unwrap_or_else(|| pretty::RcDoc::text(#name)).group()
+                        #doc
                     }
+
+                    #cells
+
+                    #field_docs
+
+                    #river_width
                 }
             }
             .into()
@@ -320,74 +1120,459 @@ pub fn derive_to_doc(item: TokenStream) -> TokenStream {
     }
 }
 
+// todoc!(opts, ...) builds a doc the way the derive does. See the rules at the bottom.
+#[proc_macro]
+pub fn todoc(input: TokenStream) -> TokenStream {
+    let mut toks = TokenStream2::from(input).into_iter();
+    let opts = match toks.next() {
+        Some(TokenTree::Ident(opts)) => opts,
+        _ => panic!("todoc! starts with the options: todoc!(opts, ...)"),
+    };
+    match toks.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+        _ => panic!("expected , after the options in todoc!"),
+    }
+    todoc_items(toks.collect(), &opts).into()
+}
+
+fn todoc_items(stream: TokenStream2, opts: &Ident) -> TokenStream2 {
+    let mut doc = quote! { pretty::RcDoc::nil() };
+    let mut toks = stream.into_iter();
+    while let Some(tok) = toks.next() {
+        let item = match tok {
+            TokenTree::Literal(lit) if lit.to_string().starts_with('"') => {
+                quote! { #opts.keyword(#lit) }
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                let node = group.stream();
+                quote! { (#node).to_doc_with(#opts) }
+            }
+            TokenTree::Ident(ident) => match ident.to_string().as_str() {
+                "line" | "line_" | "softline" | "softline_" | "hardline" => {
+                    quote! { pretty::RcDoc::#ident() }
+                }
+                "space" => quote! { pretty::RcDoc::text(" ") },
+                func @ ("raw" | "doc" | "group" | "nest") => {
+                    let args = match toks.next() {
+                        Some(TokenTree::Group(group))
+                            if group.delimiter() == Delimiter::Parenthesis =>
+                        {
+                            group.stream()
+                        }
+                        _ => panic!("expected (...) after {func} in todoc!"),
+                    };
+                    match func {
+                        "raw" => quote! { pretty::RcDoc::text(#args) },
+                        "doc" => quote! { (#args) },
+                        "group" => {
+                            let inner = todoc_items(args, opts);
+                            quote! { #inner.group() }
+                        }
+                        _ => {
+                            let inner = todoc_items(args, opts);
+                            quote! { #inner.nest(#opts.indent as isize) }
+                        }
+                    }
+                }
+                _ => panic!("unknown todoc! item: {ident}"),
+            },
+            tok => panic!("unexpected todoc! token: {tok}"),
+        };
+        doc = quote! { #doc.append(#item) };
+    }
+    doc
+}
+
 type TokenStream2 = proc_macro2::TokenStream;
 
 struct FromField {
     doc: TokenStream2,
     attrs: Attrs,
+    clause: Option<Clause>,
+    flatten: bool,
 }
 
-// ident is something like self.blah, name is blah.
-fn from_field(field: &Field, ident: &Ident, name: &str) -> FromField {
-    let mut attrs = Attrs::new(&field.attrs);
+// A clause's body is bound to a local before the fields' docs are built so the river width can
+// be computed from the clauses that are present. A flattened field has no body; its width is that
+// of its own clauses.
+struct Clause {
+    body: TokenStream2,
+    width: TokenStream2,
+}
+
+// ident is the field's binding, name is the doc of its derived name.
+// indent is the container's indentation, used unless the field sets its own.
+fn from_field(field: &Field, ident: &Ident, name: &TokenStream2, indent: &Indent) -> FromField {
+    from_field_attrs(field, Attrs::new(&field.attrs), ident, name, indent)
+}
+
+fn from_field_attrs(
+    field: &Field,
+    mut attrs: Attrs,
+    ident: &Ident,
+    name: &TokenStream2,
+    indent: &Indent,
+) -> FromField {
+    let indent = attrs.indent(indent);
+    // A flattened field's doc is its list of (doc, separator) pairs.
+    if attrs.remove("flatten").is_some() {
+        return FromField {
+            doc: quote! { #ident.to_doc_fields(opts, river_width) },
+            attrs,
+            clause: Some(Clause {
+                body: quote! {},
+                width: quote! { #ident.river_width(opts) },
+            }),
+            flatten: true,
+        };
+    }
     if let Some(doc_fn) = attrs.remove("doc_fn") {
         let doc_fn = Ident::new(&doc_fn, syn::__private::Span::call_site());
-        let doc = quote! { #doc_fn(&self) };
-        return FromField { attrs, doc };
+        let doc = quote! { #doc_fn(&self, opts) };
+        let doc = attrs.skip(doc, ident, &field.ty);
+        return FromField {
+            attrs,
+            doc,
+            clause: None,
+            flatten: false,
+        };
     }
+    let clause = attrs.remove("clause").is_some();
     let name = attrs.rename(name);
-    let doc = if is_bool(&field) {
-        let doc = quote! { #ident.then(|| pretty::RcDoc::text(#name)) };
+    let name = if is_vec(field) || is_map(field) {
+        attrs.number(name, &quote! { #ident })
+    } else {
+        name
+    };
+    let doc = if is_bool(field) {
+        // A flag is its name alone, so it has no body to hang under the keyword.
+        if clause {
+            panic!("unsupported: clause on a bool field");
+        }
+        let doc = quote! { #ident.then(|| #name) };
         let doc = attrs.els(doc);
-        doc
-    } else if is_vec(&field) {
-        let sep = attrs.separator(",");
+        attrs.show_empty(doc)
+    } else if is_vec(field) || is_map(field) {
+        let list = attrs.list(ident, is_map(field));
         let doc = quote! { if #ident.is_empty() {
                 None
             } else {
-                Some(
-                    pretty::RcDoc::intersperse(
-                        #ident.iter().map(|v| v.to_doc()),
-                        #sep
-                    )
-                    .group()
-                )
+                Some(#list)
             }
         };
-        let doc = attrs.name(doc, &name);
-        let doc = attrs.show_empty(doc);
-        doc
-    } else if is_option(&field) {
-        let doc = quote! { #ident.as_ref().map(|opt| opt.to_doc()) };
-        let doc = attrs.name(doc, &name);
+        let doc = attrs.delimiters(doc, &indent);
+        let grouping = attrs.grouping();
+        let group = attrs.max_flat_items(ident, grouping);
+        let doc = quote! { #doc.map(#group) };
+        let doc = attrs.parens(doc, &indent);
+        let doc = if clause {
+            doc
+        } else {
+            attrs.name(doc, &name, &indent)
+        };
         let doc = attrs.els(doc);
-        doc
+        attrs.show_empty(doc)
+    } else if is_option_bool(field) {
+        // Its doc is the spelling itself, so there is no name to drop and no body to wrap.
+        if clause || attrs.has("parens") || attrs.has("no_name") {
+            panic!("unsupported: clause, parens or no_name on an Option<bool> field");
+        }
+        let (yes, no) = match (attrs.spelling("true"), attrs.spelling("false")) {
+            (None, None) => panic!("Option<bool> fields need true = \"...\" or false = \"...\""),
+            (yes, no) => (
+                yes.map_or_else(
+                    || quote! { None },
+                    |yes| quote! { Some(opts.keyword(#yes)) },
+                ),
+                no.map_or_else(|| quote! { None }, |no| quote! { Some(opts.keyword(#no)) }),
+            ),
+        };
+        let doc = quote! { #ident.and_then(|b| if b { #yes } else { #no }) };
+        let doc = attrs.els(doc);
+        attrs.show_empty(doc)
+    } else if is_option(field) {
+        let doc = quote! { #ident.as_ref().map(|opt| opt.to_doc_with(opts)) };
+        let doc = attrs.parens(doc, &indent);
+        let doc = if clause {
+            doc
+        } else {
+            attrs.name(doc, &name, &indent)
+        };
+        let doc = attrs.els(doc);
+        attrs.show_empty(doc)
     } else {
-        quote! { Some(#ident.to_doc()) }
+        let doc = quote! { Some(#ident.to_doc_with(opts)) };
+        attrs.parens(doc, &indent)
+    };
+    let doc = attrs.skip(doc, ident, &field.ty);
+    let (doc, clause) = if clause {
+        let body = Ident::new(
+            &format!("__clause{ident}"),
+            syn::__private::Span::call_site(),
+        );
+        let nested = indent.hang(name.clone(), quote! { doc });
+        let aligned = Indent::Align.apply(quote! { doc });
+        let clause = Clause {
+            body: quote! { let #body = #doc; },
+            width: quote! { #body.as_ref().map(|_| opts.width(&#name).unwrap_or(0)) },
+        };
+        // River padding only shows up when the statement breaks.
+        let doc = quote! { #body.map(|doc| match river_width {
+            Some(width) => {
+                let keyword = #name;
+                opts.padding(width - opts.width(&keyword).unwrap_or(0))
+                    .flat_alt(pretty::RcDoc::nil())
+                    .append(keyword)
+                    .append(pretty::RcDoc::text(" "))
+                    .append(#aligned)
+            }
+            None => #nested,
+        }) };
+        (doc, Some(clause))
+    } else {
+        (doc, None)
     };
     let doc = attrs.prefix(doc);
     let doc = attrs.suffix(doc);
-    let doc = attrs.nest(doc);
-    FromField { doc, attrs }
+    let doc = attrs.nest(doc, &indent);
+    FromField {
+        doc,
+        attrs,
+        clause,
+        flatten: false,
+    }
+}
+
+enum TemplatePart {
+    Text(String),
+    Field(String),
+}
+
+// Splits a template into words at whitespace, each made of text and {field} placeholders.
+fn parse_template(template: &str) -> Vec<Vec<TemplatePart>> {
+    template
+        .split_whitespace()
+        .map(|word| {
+            let mut parts = Vec::new();
+            let mut rest = word;
+            while let Some(open) = rest.find('{') {
+                if open > 0 {
+                    parts.push(TemplatePart::Text(rest[..open].to_string()));
+                }
+                let close = rest[open..]
+                    .find('}')
+                    .unwrap_or_else(|| panic!("unclosed {{ in template: {template}"));
+                parts.push(TemplatePart::Field(
+                    rest[open + 1..open + close].to_string(),
+                ));
+                rest = &rest[open + close + 1..];
+            }
+            if rest.contains('}') {
+                panic!("unopened }} in template: {template}");
+            }
+            if !rest.is_empty() {
+                parts.push(TemplatePart::Text(rest.to_string()));
+            }
+            parts
+        })
+        .collect()
+}
+
+// Lays out a struct's fields by its template. The words next to a placeholder are separated by
+// nested lines and the others by spaces; a word whose placeholders are all missing is left out
+// along with its text. Every field that isn't ignored must appear exactly once.
+fn template_fields(
+    fields: &Fields,
+    template: &str,
+    naming: &Naming,
+    grouping: Grouping,
+    indent: &Indent,
+) -> (TokenStream2, TokenStream2) {
+    let fields = match fields {
+        Fields::Named(fields) => fields,
+        _ => panic!("template needs {{..}} fields"),
+    };
+    let mut ignored = false;
+    let mut idents = Vec::new();
+    let mut docs = HashMap::new();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap();
+        let name = naming.keyword(ident);
+        let bound = binding(ident);
+        let FromField {
+            doc,
+            mut attrs,
+            clause,
+            flatten,
+        } = from_field(field, &bound, &name, indent);
+        if clause.is_some() || flatten {
+            panic!("unsupported: clause or flatten in a template");
+        }
+        if attrs.remove("ignore").is_some() {
+            ignored = true;
+            continue;
+        }
+        idents.push(quote! { #ident: #bound });
+        docs.insert(ident.unraw().to_string(), doc);
+    }
+    let words = parse_template(template)
+        .into_iter()
+        .map(|parts| {
+            let mut placeholders = Vec::new();
+            let parts = parts
+                .into_iter()
+                .map(|part| match part {
+                    TemplatePart::Text(text) => quote! { opts.keyword(#text) },
+                    TemplatePart::Field(field) => {
+                        let doc = docs.remove(&field).unwrap_or_else(|| {
+                            panic!("template field {field} is unknown, ignored or used twice")
+                        });
+                        placeholders.push(doc);
+                        quote! { fields.next().unwrap().unwrap_or_else(pretty::RcDoc::nil) }
+                    }
+                })
+                .collect::<Vec<_>>();
+            // Each word is (whether it's only text, its doc if it's present).
+            if placeholders.is_empty() {
+                return quote! { (true, Some(pretty::RcDoc::nil()#(.append(#parts))*)) };
+            }
+            quote! { (false, {
+                let fields = [#(#placeholders),*];
+                if fields.iter().all(Option::is_none) {
+                    None
+                } else {
+                    let mut fields = fields.into_iter();
+                    Some(pretty::RcDoc::nil()#(.append(#parts))*)
+                }
+            }) }
+        })
+        .collect::<Vec<_>>();
+    if let Some(field) = docs.keys().next() {
+        panic!("field {field} is missing from the template");
+    }
+    if ignored {
+        idents.push(quote! { .. });
+    }
+    // Runs of text words stay together, like CREATE TABLE, so the lines only go next to fields.
+    // The words after the first, and any lines inside the fields, are nested when it breaks.
+    let doc = indent.apply(quote! { {
+        let (_, doc) = words.fold(first, |(prev, acc), (text, word)| {
+            let sep = if prev && text {
+                pretty::RcDoc::text(" ")
+            } else {
+                pretty::RcDoc::line()
+            };
+            (text, acc.append(sep).append(word))
+        });
+        doc
+    } });
+    let group = grouping.apply(doc);
+    let doc = quote! { {
+        let mut words = [#(#words),*]
+            .into_iter()
+            .filter_map(|(text, word)| word.map(|word| (text, word)));
+        words
+            .next()
+            .map(|first| #group)
+            .unwrap_or_else(pretty::RcDoc::nil)
+    } };
+    (quote! { {#(#idents),*} }, doc)
+}
+
+// A transparent struct or variant renders its single field with no name or group of its own.
+struct Transparent {
+    pattern: TokenStream2,
+    doc: TokenStream2,
+    // The field to_doc_cells and to_doc_fields are handed to, when it's a node without attributes.
+    inner: Option<Ident>,
+}
+
+fn transparent(fields: &Fields, indent: &Indent) -> Transparent {
+    if fields.len() != 1 {
+        panic!("transparent needs exactly one field");
+    }
+    let field = fields.iter().next().unwrap();
+    // A flag's only text is its name, which transparent drops.
+    if is_bool(field) || is_option_bool(field) {
+        panic!("transparent needs a node, list or map field, not a flag");
+    }
+    let (pattern, ident) = match &field.ident {
+        Some(ident) => {
+            let bound = binding(ident);
+            (quote! { { #ident: #bound } }, bound)
+        }
+        None => {
+            let ident = Ident::new("_0", syn::__private::Span::call_site());
+            (quote! { (#ident) }, ident)
+        }
+    };
+    let node = !(is_vec(field) || is_map(field) || is_option(field));
+    let mut attrs = Attrs::new(&field.attrs);
+    if is_vec(field) || is_map(field) || is_option(field) {
+        attrs.0.insert("no_name".to_string(), String::new());
+    }
+    let inner = (node && attrs.0.is_empty()).then(|| ident.clone());
+    let field = from_field_attrs(
+        field,
+        attrs,
+        &ident,
+        &quote! { pretty::RcDoc::nil() },
+        indent,
+    );
+    if field.clause.is_some() || field.flatten {
+        panic!("unsupported: clause or flatten on a transparent field");
+    }
+    let doc = field.doc;
+    Transparent {
+        pattern,
+        doc: quote! { #doc.unwrap_or_else(pretty::RcDoc::nil) },
+        inner,
+    }
 }
 
 struct FromFields {
     fields: TokenStream2,
     doc: TokenStream2,
+    // Only {..} fields can be split into cells. Evaluates to the (left, right) docs for the field
+    // named by cell.
+    cells: Option<TokenStream2>,
+    // Likewise only {..} fields. Evaluates to the present fields' (doc, separator) pairs.
+    field_docs: Option<TokenStream2>,
+    // Only {..} fields with clauses, flattened or not. Evaluates to the widest clause keyword that
+    // is present.
+    river_width: Option<TokenStream2>,
 }
 
-fn from_fields(fields: &Fields, name: &str, separator: TokenStream2) -> FromFields {
+fn from_fields(
+    fields: &Fields,
+    name: &TokenStream2,
+    naming: &Naming,
+    separator: TokenStream2,
+    grouping: Grouping,
+    indent: &Indent,
+    called: bool,
+) -> FromFields {
     match fields {
-        Fields::Named(fields) => named_fields(fields, separator),
-        Fields::Unnamed(fields) => unnamed_fields(fields, name),
+        Fields::Named(fields) => named_fields(fields, naming, separator, grouping, indent),
+        Fields::Unnamed(fields) => unnamed_fields(fields, name, grouping, indent, called),
         Fields::Unit => FromFields {
             fields: quote! {},
-            doc: quote! { Some(pretty::RcDoc::text(#name)) },
+            doc: quote! { Some(#name) },
+            cells: None,
+            field_docs: None,
+            river_width: None,
         },
     }
 }
 
-fn unnamed_fields(fields: &FieldsUnnamed, name: &str) -> FromFields {
+// called is whether the container renders as a call, whose name already heads the field.
+fn unnamed_fields(
+    fields: &FieldsUnnamed,
+    name: &TokenStream2,
+    grouping: Grouping,
+    indent: &Indent,
+    called: bool,
+) -> FromFields {
     let idents = (0..fields.unnamed.len())
         .map(|i| {
             // TODO: Better way to do this?
@@ -395,10 +1580,24 @@ fn unnamed_fields(fields: &FieldsUnnamed, name: &str) -> FromFields {
         })
         .collect::<Vec<_>>();
     let doc = match fields.unnamed.len() {
-        0 => quote! { Some(pretty::RcDoc::text(#name)) },
+        0 => quote! { Some(#name) },
         1 => {
-            let field = from_field(fields.unnamed.first().unwrap(), &idents[0], name);
-            field.doc
+            let field = fields.unnamed.first().unwrap();
+            let mut attrs = Attrs::new(&field.attrs);
+            let named = is_vec(field) || is_map(field) || is_option(field);
+            if called && named && !is_option_bool(field) {
+                attrs.0.insert("no_name".to_string(), String::new());
+            }
+            let field = from_field_attrs(field, attrs, &idents[0], name, indent);
+            if field.flatten {
+                panic!("unsupported: flatten on unnamed field");
+            }
+            if field.clause.is_some() {
+                panic!("unsupported: clause on unnamed field");
+            }
+            let doc = field.doc;
+            let group = grouping.apply(quote! { doc });
+            quote! { #doc.map(|doc| #group) }
         }
         _ => panic!(
             "unsupported: unnamed fields with len {}",
@@ -409,32 +1608,175 @@ fn unnamed_fields(fields: &FieldsUnnamed, name: &str) -> FromFields {
     FromFields {
         fields: idents,
         doc,
+        cells: None,
+        field_docs: None,
+        river_width: None,
     }
 }
 
-fn named_fields(fields: &FieldsNamed, separator: TokenStream2) -> FromFields {
+fn named_fields(
+    fields: &FieldsNamed,
+    naming: &Naming,
+    separator: TokenStream2,
+    grouping: Grouping,
+    indent: &Indent,
+) -> FromFields {
     let mut ignored = false;
-    let (docs, mut idents): (Vec<_>, Vec<_>) = fields
+    let mut clauses = Vec::new();
+    let fields = fields
         .named
         .iter()
         .filter_map(|field| {
             let ident = field.ident.as_ref().unwrap();
-            let FromField { doc, mut attrs } = from_field(field, ident, &fmt_ident(ident));
+            let name = naming.keyword(ident);
+            let bound = binding(ident);
+            let FromField {
+                doc,
+                mut attrs,
+                clause,
+                flatten,
+            } = from_field(field, &bound, &name, indent);
+            let order = attrs.remove("order").map(|order| {
+                order
+                    .parse::<usize>()
+                    .unwrap_or_else(|_| panic!("order must be a number: {order}"))
+            });
             if attrs.remove("ignore").is_some() {
+                if attrs.has("break_before") {
+                    panic!("break_before and ignore are exclusive");
+                }
                 ignored = true;
                 None
             } else {
-                Some((doc, quote! { #ident }))
+                clauses.extend(clause);
+                if matches!(grouping, Grouping::NeverBreak)
+                    && attrs.0.get("break_before").map(String::as_str) == Some("hardline")
+                {
+                    panic!("break_before = \"hardline\" and never_break are exclusive");
+                }
+                let sep = attrs.break_before(&separator);
+                // Each field contributes a list of docs so flattened fields can add several. The
+                // first flattened doc takes this field's separator.
+                let docs = if flatten {
+                    quote! { {
+                        let mut docs = #doc
+                            .into_iter()
+                            .map(|(doc, sep)| (Some(doc), sep))
+                            .collect::<Vec<_>>();
+                        if let Some(first) = docs.first_mut() {
+                            first.1 = #sep;
+                        }
+                        docs
+                    } }
+                } else {
+                    quote! { vec![(#doc, #sep)] }
+                };
+                Some((order, docs, ident.clone(), bound))
             }
         })
+        .collect::<Vec<_>>();
+    // Fields with an order take that position, and the others fill the free ones in declaration
+    // order.
+    let mut slots = fields.iter().map(|_| None).collect::<Vec<_>>();
+    let mut rest = Vec::new();
+    for field in fields {
+        match field.0 {
+            Some(order) => match slots.get_mut(order) {
+                Some(slot @ None) => *slot = Some(field),
+                Some(Some(_)) => panic!("two fields have order = {order}"),
+                None => panic!("order = {order} is past the last field"),
+            },
+            None => rest.push(field),
+        }
+    }
+    let mut rest = rest.into_iter();
+    let fields = slots
+        .into_iter()
+        .map(|slot| slot.or_else(|| rest.next()).unwrap())
+        .collect::<Vec<_>>();
+    let names = fields
+        .iter()
+        .map(|(_, _, ident, _)| ident.unraw().to_string())
+        .collect::<Vec<_>>();
+    let (docs, mut idents): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .map(|(_, doc, ident, bound)| (doc, quote! { #ident: #bound }))
         .unzip();
+    // The break before the first present field is dropped.
+    let group = grouping.apply(quote! {
+        docs.fold(first, |acc, (doc, sep)| acc.append(sep).append(doc))
+    });
+    let bodies = clauses
+        .iter()
+        .map(|clause| &clause.body)
+        .collect::<Vec<_>>();
+    let widths = clauses.iter().map(|clause| &clause.width);
+    let widest = quote! { [#(#widths),*].into_iter().flatten().max() };
+    // The outermost container picks the river width for the clauses of the fields it flattens, so
+    // to_doc_fields takes it as an argument instead.
+    let river = if clauses.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            #(#bodies)*
+            let river_width = match opts.clause_layout {
+                ClauseLayout::Nested => None,
+                ClauseLayout::River => #widest,
+            };
+        }
+    };
+    let river_width = (!clauses.is_empty()).then(|| {
+        quote! { {
+            #(#bodies)*
+            #widest
+        } }
+    });
     let doc = quote! { {
-       let docs = [#(#docs),*].into_iter().filter_map(|v| v).collect::<Vec<_>>();
-       if docs.is_empty() {
-           None
-       } else {
-           Some(pretty::RcDoc::intersperse(docs, #separator).group())
-       }
+       #river
+       let mut docs = [#(#docs),*]
+           .into_iter()
+           .flatten()
+           .filter_map(|(doc, sep)| doc.map(|doc| (doc, sep)));
+       docs.next().map(|(first, _)| #group)
+    } };
+    let field_docs = quote! { {
+        #(#bodies)*
+        [#(#docs),*]
+            .into_iter()
+            .flatten()
+            .filter_map(|(doc, sep)| doc.map(|doc| (doc, sep)))
+            .collect()
+    } };
+    // The right cell starts at the named field. Without both cells the whole doc is used.
+    let join = |docs: TokenStream2| {
+        quote! { {
+            let mut docs = #docs.into_iter();
+            docs.next().map(|(first, _)| #group)
+        } }
+    };
+    let left = join(quote! { left });
+    let right = join(quote! { right });
+    let cells = quote! { {
+        #river
+        let docs = [#(#docs),*];
+        let at = [#(#names),*].iter().position(|name| *name == cell);
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for (i, docs) in docs.into_iter().enumerate() {
+            for (doc, sep) in docs {
+                if let Some(doc) = doc {
+                    if at.map_or(false, |at| i >= at) {
+                        right.push((doc, sep));
+                    } else {
+                        left.push((doc, sep));
+                    }
+                }
+            }
+        }
+        match (#left, #right) {
+            (Some(left), Some(right)) => (left, Some(right)),
+            _ => (self.to_doc_with(opts), None),
+        }
     } };
     if ignored {
         idents.push(quote! { .. });
@@ -443,6 +1785,9 @@ fn named_fields(fields: &FieldsNamed, separator: TokenStream2) -> FromFields {
     FromFields {
         fields: idents,
         doc,
+        cells: Some(cells),
+        field_docs: Some(field_docs),
+        river_width,
     }
 }
 
@@ -452,13 +1797,160 @@ For a struct with {..} fields: apply the rules for {..}
 For a struct with () fields:
 For a enum: current variant converted to doc
 
+Docs are built by to_doc_with(&self, opts: &FormatOptions), which the derive calls recursively
+on children. The crate using the derive defines the ToDoc trait, FormatOptions, ListStyle and
+ClauseLayout and KeywordCase. A plain to_doc() on the trait is a shortcut for to_doc_with with the default
+options. doc_fn = "f" on a field calls f(&self, opts) for the field's doc.
+
+Keywords (derived type, variant and field names, rename, nest, nest_suffix, else, and the other
+keyword attributes) are built with opts.keyword(text), which applies opts.keyword_case at render
+time; the default case leaves them as spelled. prefix, suffix, item_prefix and item_suffix are
+plain text, like values, separators and delimiters. rename, nest and else also take per-dialect
+spellings, as in rename(postgres = "...", mysql = "..."), chosen by opts.dialect; other dialects
+use the plain spelling (for rename, the derived name if there is none).
+
+Names: type, variant and field names are split into words at underscores and case changes (a run
+of capitals is one word, so SQLServer is SQL SERVER) and uppercased. Type names lose a trailing
+Statement (or sStatement); strip_suffix = "..." on the struct or enum strips that suffix from its
+names instead. rename_all = "lower spaced", "verbatim" (as written) or "snake" on the struct or
+enum changes how its variant and field names are spelled. Those spellings were asked for
+explicitly, so they are plain text that opts.keyword_case leaves alone. This is the one exception
+to keyword casing: the type's own name and a rename on an item still win over rename_all and are
+keywords like any other.
+
+template = "KEYWORD {field} (...)" on a struct lays out its fields by the template instead. Words
+with a placeholder are separated from their neighbours by lines, nested by indent after the first,
+and words of plain text next to each other by spaces; text is keywords, {field} is that
+field's doc, and a word whose fields are all missing is left out. Each field that isn't ignored
+appears exactly once. count, singular and plural don't apply, since there is no name.
+
+call = "NAME" on a struct or variant renders it as NAME( + its fields + ) in place of its name,
+with the fields nested on their own lines when it breaks. A single unnamed list or Option field
+leaves out its name, as if it were no_name.
+
+transparent on a struct, enum or variant with a single field renders just that field, without
+the container's name or group. The field can't be a bool or Option<bool>, whose only text is its
+name.
+
 Converting a Field to a doc:
 
 () [empty variant or tuple or struct()]: enum variant name or struct field name
 bool: field name if true, nil if false
 struct/enum: recursive call
 {..}: for each field in order, convert to doc, then intersperse with line
+  order = N on a field renders it at position N (from 0) among the fields that aren't ignored;
+  fields without one fill the other positions in declaration order. So on {a, b, c}, order = 0
+  on c renders c, a, b and order = 2 on a renders b, c, a.
+  break_before on a field replaces the separator before it with line, softline (nothing when
+  flat), hardline (always break) or glue (nothing at all). It can't be combined with ignore.
+  clause on a field (not a bool) makes its name a clause keyword. With opts.clause_layout set
+  to River, the keywords of the present clauses are right-aligned to the widest one (as
+  rendered, measured with opts.width) and the bodies line up after them; otherwise the body is
+  nested under the keyword as usual.
+  parens on a field wraps its doc (inside its name, prefix and suffix) in parentheses, with the
+  body nested on its own lines when it doesn't fit.
+  flatten on a field whose type is a {..} struct splices that struct's field docs (from
+  to_doc_fields) into this one's group in place of a nested group of its own. Its clauses share
+  the river of the outermost container, which passes the width down to to_doc_fields.
+Option<bool>: nothing if None, otherwise the true = "..." or false = "..." spelling (nothing if
+  that one isn't given). clause, parens and no_name don't apply to it.
 Option<T>: nil if None, otherwise field name nested with T converted to doc
 Vec<T>: nil if empty, otherwise field name nested with values converted to docs, interspersed with comma line.
+  The list style picks where the separator goes: after each value (trailing), before each value
+  (leading), or after each value plus the last one when the list breaks (trailing_when_broken).
+  Without a list_style attribute the style comes from opts.list_style at runtime.
+  With fill each separator breaks on its own, packing as many values per line as fit.
+  max_flat_items = N always breaks lists with more than N values.
+  align_cell = "field" splits each value at the named field with to_doc_cells and, when the list
+  breaks, pads the part before it so the cells line up in a column. The padding goes by display
+  width (opts.width), and a value whose left part doesn't fit on one line isn't padded.
+  item_prefix and item_suffix wrap each value rather than the whole list. keyword_separator =
+  "KEYWORD" separates the values with KEYWORD on a line of its own instead of a separator.
+  With delimiters the values are wrapped in open + softline + nested values + softline + close, grouped together.
+  singular = "..." and plural = "..." replace the field name, picked by the number of values, and
+  can't be combined with rename.
+  On a struct or variant they replace its name, counting the list field named by count = "...".
+HashMap<K, V>/BTreeMap<K, V>: like Vec<T>, with each entry converted to key = value, in key order,
+  so K must be Ord.
+
+Missing fields (false, None, an empty Vec or map) are left out. else = "TEXT" on any of them shows
+TEXT instead, and show_empty keeps a nil placeholder; the two are exclusive.
+skip_if = "path::to::fn" leaves out any field for which fn(&value) is true, and skip_default one
+that equals its type's Default. ignore leaves the field out unconditionally.
+
+Indentation: nesting indents by opts.indent at runtime. nest_width = N on an enum, variant,
+struct or field overrides it for that node and the fields inside it. align instead puts the
+body on the same line after its keyword (or open delimiter) and lines continuations up with it,
+through opts.align(doc). Padding that lines text up in a column (river and align_cell) is
+opts.padding(width), so with opts.tabs only nesting turns into tabs.
+
+Groups: structs, variants with fields and lists are each grouped. never_break renders the
+group flat regardless of width, through opts.flat(doc), so none of its fields can have
+break_before = "hardline". always_break forces it to break, and no_group leaves the separators
+in the enclosing group.
+
+todoc!(opts, ...) builds a doc for hand-written impls from items that are appended in order:
+"KEYWORD" is opts.keyword("KEYWORD"), {expr} is expr.to_doc_with(opts), raw(expr) is text that
+isn't a keyword, doc(expr) is an existing doc, group(...) and nest(...) group or nest (by
+opts.indent) the items inside, and line, line_, softline, softline_, hardline and space are
+separators.
 
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str) -> Ident {
+        Ident::new(name, syn::__private::Span::call_site())
+    }
+
+    #[test]
+    fn split_words() {
+        assert_eq!(super::split_words("DropRoles"), ["Drop", "Roles"]);
+        assert_eq!(super::split_words("if_exists"), ["if", "exists"]);
+        assert_eq!(super::split_words("__a__b_"), ["a", "b"]);
+        // A run of capitals is one word, up to the capital that starts the next one.
+        assert_eq!(super::split_words("SQLServer"), ["SQL", "Server"]);
+        assert_eq!(super::split_words("ShowSQL"), ["Show", "SQL"]);
+        assert_eq!(super::split_words("IPAddr_v4"), ["IP", "Addr", "v4"]);
+        // Digits end a word like lowercase letters do.
+        assert_eq!(super::split_words("Md5Hash"), ["Md5", "Hash"]);
+        assert_eq!(super::split_words("V2"), ["V2"]);
+        // Non-ASCII names split by char.
+        assert_eq!(super::split_words("öffnenÜber"), ["öffnen", "Über"]);
+        assert_eq!(super::split_words("ÄÖÜber"), ["ÄÖ", "Über"]);
+    }
+
+    #[test]
+    fn fmt_ident() {
+        assert_eq!(super::fmt_ident(&ident("SQLServer")), "SQL SERVER");
+        assert_eq!(super::fmt_ident(&ident("öffnenÜber")), "ÖFFNEN ÜBER");
+        assert_eq!(super::fmt_ident(&ident("DropRolesStatement")), "DROP ROLE");
+        assert_eq!(
+            super::fmt_ident(&ident("CreateTableStatement")),
+            "CREATE TABLE"
+        );
+    }
+
+    #[test]
+    fn strip_suffix() {
+        let naming = Naming {
+            strip_suffix: Some("Stmt".to_string()),
+            rename_all: RenameAll::UpperSpaced,
+        };
+        assert_eq!(naming.fmt(&ident("ShowStmt")), "SHOW");
+        // Statement is only stripped without a strip_suffix of its own.
+        assert_eq!(naming.fmt(&ident("ShowStatement")), "SHOW STATEMENT");
+        // A name that is all suffix keeps it.
+        assert_eq!(naming.fmt(&ident("Stmt")), "STMT");
+        let naming = Naming {
+            strip_suffix: Some(String::new()),
+            rename_all: RenameAll::Snake,
+        };
+        assert_eq!(
+            naming.fmt(&ident("DropRolesStatement")),
+            "drop_roles_statement"
+        );
+    }
+}
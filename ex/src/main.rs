@@ -5,12 +5,243 @@ use std::fmt;
 use mz_sql_parser::ast::display::AstDisplay;
 use mz_sql_parser::ast::{display::AstFormatter, Ident};
 use mz_sql_parser::ast::{AstInfo, UnresolvedDatabaseName, UnresolvedObjectName};
-use pretty::{Doc, RcDoc};
+use pretty::{Doc, DocAllocator, RcDoc};
+use unicode_width::UnicodeWidthStr;
 
 use astdisplay::*;
 
 trait ToDoc {
-    fn to_doc(&self) -> RcDoc<()>;
+    fn to_doc_with(&self, opts: &FormatOptions) -> RcDoc<()>;
+
+    fn to_doc(&self) -> RcDoc<()> {
+        self.to_doc_with(&FormatOptions::default())
+    }
+
+    /// Splits the doc into the part before the field named `cell` and the part starting at it,
+    /// so lists can line the cells up. Types that can't be split return the whole doc.
+    fn to_doc_cells(&self, opts: &FormatOptions, cell: &str) -> (RcDoc<()>, Option<RcDoc<()>>) {
+        (self.to_doc_with(opts), None)
+    }
+
+    /// The docs of the fields, each with the separator that goes before it, so a parent can
+    /// splice them into its own group. Types without fields return the whole doc. Clauses line up
+    /// on `river_width`, which the parent picks.
+    fn to_doc_fields(
+        &self,
+        opts: &FormatOptions,
+        river_width: Option<usize>,
+    ) -> Vec<(RcDoc<()>, RcDoc<()>)> {
+        vec![(self.to_doc_with(opts), RcDoc::line())]
+    }
+
+    /// The width of the widest clause keyword that `to_doc_fields` would render, if any.
+    fn river_width(&self, opts: &FormatOptions) -> Option<usize> {
+        None
+    }
+}
+
+struct FormatOptions {
+    list_style: ListStyle,
+    /// Width of one level of nesting.
+    indent: usize,
+    /// Render each level of nesting as a tab instead of `indent` spaces. Alignment stays spaces.
+    tabs: bool,
+    clause_layout: ClauseLayout,
+    keyword_case: KeywordCase,
+    dialect: Dialect,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            list_style: ListStyle::default(),
+            indent: 4,
+            tabs: false,
+            clause_layout: ClauseLayout::default(),
+            keyword_case: KeywordCase::default(),
+            dialect: Dialect::default(),
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Keyword text in the configured case. Identifiers, literals, the `prefix` and `suffix`
+    /// strings and names spelled by a `rename_all` other than `UPPER SPACED` don't go through here.
+    fn keyword<'a>(&self, keyword: &str) -> RcDoc<'a> {
+        let keyword = match self.keyword_case {
+            KeywordCase::Upper => keyword.to_uppercase(),
+            KeywordCase::Lower => keyword.to_lowercase(),
+            KeywordCase::Capitalized => keyword
+                .split(' ')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first
+                            .to_uppercase()
+                            .chain(chars.flat_map(char::to_lowercase))
+                            .collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            KeywordCase::Preserve => keyword.to_string(),
+        };
+        RcDoc::text(keyword)
+    }
+
+    /// Display width of `doc` laid out flat, or None if it always breaks.
+    fn width(&self, doc: &RcDoc<()>) -> Option<usize> {
+        let text = doc.clone().group().pretty(usize::MAX).to_string();
+        if text.contains('\n') {
+            return None;
+        }
+        let text = text.replace([ALIGN_START, ALIGN_END, PADDING], "");
+        Some(UnicodeWidthStr::width(text.as_str()))
+    }
+
+    /// Lines the continuations of `doc` up with the column it starts at.
+    fn align<'a>(&self, doc: RcDoc<'a>) -> RcDoc<'a> {
+        let doc = pretty::DocBuilder(&pretty::RcAllocator, doc.into())
+            .align()
+            .into_doc();
+        if !self.tabs {
+            return doc;
+        }
+        marker(ALIGN_START).append(doc).append(marker(ALIGN_END))
+    }
+
+    /// Spaces that pad text out to a column.
+    fn padding<'a>(&self, width: usize) -> RcDoc<'a> {
+        let padding = RcDoc::text(" ".repeat(width));
+        if !self.tabs {
+            return padding;
+        }
+        marker(PADDING).append(padding)
+    }
+
+    /// `doc` laid out flat whatever the width: lines take their flat spelling, and anything that
+    /// can only be laid out broken gives way.
+    fn flat<'a>(&self, doc: RcDoc<'a>) -> RcDoc<'a> {
+        flat(doc)
+    }
+
+    /// Renders `doc`, which must have been built with these options. With tabs, each level of
+    /// nesting becomes a tab; alignment and padding stay spaces, so lines inside an `align` keep
+    /// the tabs of the line the alignment started on.
+    fn render(&self, doc: &RcDoc<()>, width: usize) -> String {
+        let mut out = Vec::new();
+        doc.render(width, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        if !self.tabs || self.indent == 0 {
+            return out;
+        }
+        // Tab counts of the lines the open alignments started on.
+        let mut aligned: Vec<usize> = Vec::new();
+        out.lines()
+            .map(|line| {
+                let rest = line.trim_start_matches(' ');
+                let spaces = line.len() - rest.len();
+                let tabs =
+                    (spaces / self.indent).min(aligned.first().copied().unwrap_or(usize::MAX));
+                for c in rest.chars() {
+                    match c {
+                        ALIGN_START => aligned.push(tabs),
+                        ALIGN_END => {
+                            aligned.pop();
+                        }
+                        _ => {}
+                    }
+                }
+                let rest = rest.replace([ALIGN_START, ALIGN_END, PADDING], "");
+                format!(
+                    "{}{}{rest}",
+                    "\t".repeat(tabs),
+                    " ".repeat(spaces - tabs * self.indent),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn flat(doc: RcDoc) -> RcDoc {
+    match &*doc {
+        Doc::Append(left, right) => flat(left.clone()).append(flat(right.clone())),
+        Doc::Group(doc) | Doc::Nest(_, doc) | Doc::Annotated(_, doc) => flat(doc.clone()),
+        Doc::FlatAlt(_, doc) => flat(doc.clone()),
+        // The first choice is the one a flat layout that fits would take.
+        Doc::Union(doc, _) => flat(doc.clone()),
+        Doc::Column(f) => {
+            let f = f.clone();
+            pretty::RcAllocator
+                .column(move |column| flat(f(column)))
+                .into_doc()
+        }
+        Doc::Nesting(f) => {
+            let f = f.clone();
+            pretty::RcAllocator
+                .nesting(move |nesting| flat(f(nesting)))
+                .into_doc()
+        }
+        // always_break's fail, which only allows a broken layout.
+        Doc::Fail => RcDoc::nil(),
+        _ => doc,
+    }
+}
+
+// Zero-width markers that render uses to tell alignment and padding apart from nesting when it
+// swaps indentation for tabs. They're only emitted with tabs.
+const ALIGN_START: char = '\u{E000}';
+const ALIGN_END: char = '\u{E001}';
+const PADDING: char = '\u{E002}';
+
+fn marker<'a>(marker: char) -> RcDoc<'a> {
+    // RcDoc::text would measure the char, so the text doc is built by hand.
+    let text = RcDoc::from(Doc::OwnedText(marker.to_string().into()));
+    RcDoc::from(Doc::RenderLen(0, text))
+}
+
+#[derive(Clone, Copy, Default)]
+enum ListStyle {
+    /// `a,\nb`
+    #[default]
+    Trailing,
+    /// `a\n, b`
+    Leading,
+    /// `a,\nb,` when broken, `a, b` when flat.
+    TrailingWhenBroken,
+}
+
+#[derive(Clone, Copy, Default)]
+enum ClauseLayout {
+    /// `SELECT\n    a\nFROM\n    t`
+    #[default]
+    Nested,
+    /// `SELECT a\n  FROM t`
+    River,
+}
+
+#[derive(Clone, Copy, Default)]
+enum KeywordCase {
+    /// `SELECT`
+    Upper,
+    /// `select`
+    Lower,
+    /// `Select`
+    Capitalized,
+    /// As spelled in the derive: derived names are UPPER SPACED, other keywords as written.
+    #[default]
+    Preserve,
+}
+
+/// Picks between the spellings given with `rename(postgres = "...", mysql = "...")` and friends.
+#[derive(Clone, Copy, Default)]
+enum Dialect {
+    #[default]
+    Generic,
+    Postgres,
+    Mysql,
 }
 
 #[derive(AstDisplay, ToDoc)]
@@ -20,26 +251,26 @@ enum Blah {
 }
 
 impl ToDoc for Ident {
-    fn to_doc(&self) -> RcDoc<()> {
-        RcDoc::text(self.as_str())
+    fn to_doc_with(&self, opts: &FormatOptions) -> RcDoc<()> {
+        todoc!(opts, raw(self.as_str()))
     }
 }
 
 impl ToDoc for UnresolvedObjectName {
-    fn to_doc(&self) -> RcDoc<()> {
-        RcDoc::text(self.to_ast_string())
+    fn to_doc_with(&self, opts: &FormatOptions) -> RcDoc<()> {
+        todoc!(opts, raw(self.to_ast_string()))
     }
 }
 
 impl ToDoc for UnresolvedDatabaseName {
-    fn to_doc(&self) -> RcDoc<()> {
-        RcDoc::text(self.to_ast_string())
+    fn to_doc_with(&self, opts: &FormatOptions) -> RcDoc<()> {
+        todoc!(opts, raw(self.to_ast_string()))
     }
 }
 
 impl ToDoc for usize {
-    fn to_doc(&self) -> RcDoc<()> {
-        RcDoc::text(self.to_string())
+    fn to_doc_with(&self, opts: &FormatOptions) -> RcDoc<()> {
+        todoc!(opts, raw(self.to_string()))
     }
 }
 
@@ -127,11 +358,10 @@ fn main() {
     // let ast = s.to_ast_string();
     // println!("{}", ast);
     let mut prev = "".to_string();
-    let doc = s.to_doc();
+    let opts = FormatOptions::default();
+    let doc = s.to_doc_with(&opts);
     for i in 1..=100 {
-        let mut cur = Vec::new();
-        doc.render(i, &mut cur).unwrap();
-        let cur = String::from_utf8(cur).unwrap();
+        let cur = opts.render(&doc, i);
         if cur != prev {
             prev = cur;
             println!("\n{i}:\n{prev}");
@@ -143,11 +373,12 @@ fn main() {
 #[todoc(no_name)]
 struct Select /*<T: AstInfo>*/ {
     //pub distinct: Option<Distinct<T>>,
-    #[todoc(rename = "SELECT")]
+    #[todoc(rename = "SELECT", clause, align_cell = "alias")]
     pub projection: Vec<SelectItem>,
     //pub from: Vec<TableWithJoins<T>>,
-    #[todoc(rename = "WHERE")]
+    #[todoc(rename = "WHERE", align, clause)]
     pub selection: Option<Expr>,
+    #[todoc(clause)]
     pub group_by: Vec<Expr>,
     #[todoc(ignore)]
     pub having: Option<Expr>,
@@ -156,15 +387,20 @@ struct Select /*<T: AstInfo>*/ {
 
 #[derive(ToDoc)]
 //#[todoc(no_name)]
-struct Value(#[todoc(prefix = "(", suffix = ")", no_name)] Vec<Expr>);
+struct Value(#[todoc(delimiters = "()", no_name)] Vec<Expr>);
 
 #[derive(ToDoc)]
+#[todoc(transparent)]
 struct Values(Vec<Value>);
 
 #[derive(ToDoc)]
 enum SelectItem /*<T: AstInfo>*/ {
     /// An expression, optionally followed by `[ AS ] alias`.
-    Expr { expr: Expr, alias: Option<Ident> },
+    Expr {
+        expr: Expr,
+        #[todoc(rename = "AS")]
+        alias: Option<Ident>,
+    },
     /// An unqualified `*`.
     #[todoc(rename = "*")]
     Wildcard,
@@ -181,9 +417,9 @@ struct CaseCondition {
 
 #[derive(Clone, ToDoc)]
 enum Expr {
-    List(#[todoc(prefix = "LIST[", suffix = "]", no_name, show_empty)] Vec<Expr>),
+    List(#[todoc(prefix = "LIST[", suffix = "]", no_name, show_empty, fill)] Vec<Expr>),
     /// `CASE [<operand>] WHEN <condition> THEN <result> ... [ELSE <result>] END`
-    #[todoc(nest = "CASE", nest_suffix = "END")]
+    #[todoc(nest = "CASE", nest_suffix = "END", always_break)]
     Case {
         #[todoc(no_name)]
         operand: Option<Box<Expr>>,
@@ -197,7 +433,8 @@ enum Expr {
         expr: Box<Expr>,
         #[todoc(rename = "NOT")]
         negated: bool,
-        #[todoc(rename = "ILIKE", else = "LIKE")]
+        // MySQL's LIKE is already case insensitive under the default collations.
+        #[todoc(rename = "ILIKE", rename(mysql = "LIKE"), else = "LIKE")]
         case_insensitive: bool,
         pattern: Box<Expr>,
         escape: Option<Box<Expr>>,
@@ -210,6 +447,25 @@ enum Expr {
     /// Identifier e.g. table name or column name
     Identifier(#[todoc(separator = ".", no_name, separator_noline)] Vec<Ident>),
     ExpectedGroupSizeYo,
+    Function {
+        name: Ident,
+        #[todoc(delimiters = "()", no_name, break_before = "glue")]
+        args: Vec<Expr>,
+    },
+    /// `CAST(<expr> AS <data_type>)`
+    #[todoc(call = "CAST")]
+    Cast {
+        expr: Box<Expr>,
+        #[todoc(prefix = "AS ")]
+        data_type: Ident,
+    },
+    /// `COUNT([DISTINCT] <args>)`
+    #[todoc(call = "COUNT")]
+    Count {
+        distinct: bool,
+        #[todoc(no_name)]
+        args: Vec<Expr>,
+    },
     #[todoc(prefix = "$")]
     Parameter(usize),
     Not {
@@ -230,8 +486,83 @@ struct FnTest {
     b: bool,
 }
 
-fn fntest(f: &FnTest) -> Option<RcDoc> {
-    f.a.then(|| RcDoc::text("YO"))
+fn fntest<'a>(f: &'a FnTest, opts: &FormatOptions) -> Option<RcDoc<'a>> {
+    f.a.then(|| todoc!(opts, "YO"))
+}
+
+#[derive(ToDoc)]
+#[todoc(no_name)]
+struct Query {
+    #[todoc(flatten)]
+    select: Select,
+    #[todoc(flatten)]
+    limit: Limit,
+}
+
+/// `(<query>) [AS <alias>]`
+#[derive(ToDoc)]
+#[todoc(no_name)]
+struct Derived {
+    #[todoc(parens)]
+    subquery: Box<Query>,
+    #[todoc(rename = "AS")]
+    alias: Option<Ident>,
+}
+
+/// `<select> UNION ALL <select> ...`
+#[derive(ToDoc)]
+#[todoc(transparent)]
+struct UnionAll(#[todoc(keyword_separator = "UNION ALL")] Vec<Select>);
+
+#[derive(ToDoc)]
+#[todoc(no_name)]
+struct TableWithJoins {
+    relation: Ident,
+    #[todoc(no_name, item_prefix = "JOIN ", separator = "")]
+    joins: Vec<Ident>,
+}
+
+#[derive(ToDoc)]
+#[todoc(no_name)]
+struct Limit {
+    /// `usize::MAX` means no limit.
+    #[todoc(prefix = "LIMIT ", skip_if = "is_unlimited")]
+    count: usize,
+    #[todoc(prefix = "OFFSET ", skip_default)]
+    offset: usize,
+}
+
+fn is_unlimited(count: &usize) -> bool {
+    *count == usize::MAX
+}
+
+#[derive(ToDoc)]
+#[todoc(template = "CREATE {temporary} TABLE {if_not_exists} {name} ({columns})")]
+struct CreateTableStatement {
+    temporary: bool,
+    if_not_exists: bool,
+    name: Ident,
+    #[todoc(no_name, show_empty)]
+    columns: Vec<Ident>,
+}
+
+/// `<expr> [ASC | DESC] [NULLS FIRST | NULLS LAST]`
+#[derive(ToDoc)]
+#[todoc(no_name)]
+struct OrderByExpr {
+    expr: Expr,
+    #[todoc(true = "ASC", false = "DESC")]
+    asc: Option<bool>,
+    #[todoc(true = "NULLS LAST", false = "NULLS FIRST")]
+    nulls_last: Option<bool>,
+}
+
+#[derive(ToDoc)]
+#[todoc(count = "names", singular = "DROP ROLE", plural = "DROP ROLES")]
+struct DropRolesStatement {
+    if_exists: bool,
+    #[todoc(no_name)]
+    names: Vec<Ident>,
 }
 
 #[derive(ToDoc)]
@@ -239,3 +570,691 @@ struct DeallocateStatement {
     #[todoc(else = "ALL")]
     pub name: Option<Ident>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(node: &impl ToDoc, width: usize) -> String {
+        render_with(node, &FormatOptions::default(), width)
+    }
+
+    fn render_with(node: &impl ToDoc, opts: &FormatOptions, width: usize) -> String {
+        opts.render(&node.to_doc_with(opts), width)
+    }
+
+    fn ident(name: &str) -> Expr {
+        Expr::Identifier(vec![name.into()])
+    }
+
+    fn select() -> Select {
+        Select {
+            projection: vec![SelectItem::Expr {
+                expr: ident("a"),
+                alias: Some("x".into()),
+            }],
+            selection: Some(ident("b")),
+            group_by: Vec::new(),
+            having: None,
+        }
+    }
+
+    #[test]
+    fn parens() {
+        let derived = Derived {
+            subquery: Box::new(Query {
+                select: select(),
+                limit: Limit {
+                    count: usize::MAX,
+                    offset: 5,
+                },
+            }),
+            alias: Some("d".into()),
+        };
+        assert_eq!(
+            render(&derived, 80),
+            "(SELECT a AS x WHERE b OFFSET 5) AS d"
+        );
+        assert_eq!(
+            render(&derived, 10),
+            "(\n    SELECT\n        a AS\n            x\n    WHERE b\n    OFFSET 5\n)\nAS\n    d"
+        );
+    }
+
+    #[test]
+    fn delimiters() {
+        let function = Expr::Function {
+            name: "f".into(),
+            args: vec![ident("alpha"), ident("beta")],
+        };
+        assert_eq!(render(&function, 80), "f(alpha, beta)");
+        assert_eq!(render(&function, 10), "f(\n    alpha,\n    beta\n)");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Columns {
+        #[todoc(no_name, list_style = "trailing_when_broken")]
+        columns: Vec<Ident>,
+    }
+
+    #[test]
+    fn list_style() {
+        let function = Expr::Function {
+            name: "f".into(),
+            args: vec![ident("alpha"), ident("beta")],
+        };
+        let opts = FormatOptions {
+            list_style: ListStyle::Leading,
+            ..FormatOptions::default()
+        };
+        assert_eq!(render_with(&function, &opts, 80), "f(alpha, beta)");
+        assert_eq!(
+            render_with(&function, &opts, 10),
+            "f(\n    alpha\n    , beta\n)"
+        );
+        let columns = Columns {
+            columns: vec!["alpha".into(), "beta".into()],
+        };
+        assert_eq!(render_with(&columns, &opts, 80), "alpha, beta");
+        assert_eq!(render_with(&columns, &opts, 10), "alpha,\nbeta,");
+    }
+
+    #[test]
+    fn fill() {
+        let list = Expr::List(["a", "b", "c", "d", "e", "f"].map(ident).to_vec());
+        assert_eq!(render(&list, 80), "LIST[a, b, c, d, e, f]");
+        assert_eq!(render(&list, 10), "LIST[a, b,\nc, d, e,\nf]");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Breaks {
+        a: Ident,
+        #[todoc(break_before = "softline", prefix = "+")]
+        b: Ident,
+        #[todoc(break_before = "glue", prefix = "-")]
+        c: Ident,
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct HardBreak {
+        a: Ident,
+        #[todoc(break_before = "hardline")]
+        b: Ident,
+    }
+
+    #[test]
+    fn break_before() {
+        let breaks = Breaks {
+            a: "alpha".into(),
+            b: "beta".into(),
+            c: "gamma".into(),
+        };
+        assert_eq!(render(&breaks, 80), "alpha+beta-gamma");
+        assert_eq!(render(&breaks, 10), "alpha\n+beta-gamma");
+        let hard = HardBreak {
+            a: "a".into(),
+            b: "b".into(),
+        };
+        assert_eq!(render(&hard, 80), "a\nb");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name, never_break)]
+    struct NeverBreak {
+        a: Ident,
+        b: Expr,
+        c: AlwaysBreak,
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name, always_break)]
+    struct AlwaysBreak {
+        a: Ident,
+        b: Ident,
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Group {
+        a: Ident,
+        b: Ident,
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name, no_group)]
+    struct NoGroup {
+        a: Ident,
+        b: Ident,
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Groups {
+        a: Ident,
+        grouped: Group,
+        ungrouped: NoGroup,
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct MaxFlatItems {
+        #[todoc(no_name, max_flat_items = 2)]
+        items: Vec<Ident>,
+    }
+
+    #[test]
+    fn grouping() {
+        let never = NeverBreak {
+            a: "a".into(),
+            b: Expr::Function {
+                name: "f".into(),
+                args: vec![ident("alpha"), ident("beta")],
+            },
+            c: AlwaysBreak {
+                a: "x".into(),
+                b: "y".into(),
+            },
+        };
+        assert_eq!(render(&never, 80), "a f(alpha, beta) x y");
+        assert_eq!(render(&never, 5), "a f(alpha, beta) x y");
+        let groups = Groups {
+            a: "a".into(),
+            grouped: Group {
+                a: "b".into(),
+                b: "c".into(),
+            },
+            ungrouped: NoGroup {
+                a: "d".into(),
+                b: "e".into(),
+            },
+        };
+        assert_eq!(render(&groups, 80), "a b c d e");
+        assert_eq!(render(&groups, 5), "a\nb c\nd\ne");
+        let always = AlwaysBreak {
+            a: "a".into(),
+            b: "b".into(),
+        };
+        assert_eq!(render(&always, 80), "a\nb");
+        let items = |n: usize| MaxFlatItems {
+            items: ["a", "b", "c"][..n]
+                .iter()
+                .map(|&item| item.into())
+                .collect(),
+        };
+        assert_eq!(render(&items(2), 80), "a, b");
+        assert_eq!(render(&items(3), 80), "a,\nb,\nc");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(nest_width = 2)]
+    struct NestWidth {
+        #[todoc(delimiters = "()", no_name)]
+        items: Vec<Ident>,
+    }
+
+    #[test]
+    fn indent() {
+        let function = Expr::Function {
+            name: "f".into(),
+            args: vec![ident("alpha"), ident("beta")],
+        };
+        let opts = FormatOptions {
+            indent: 2,
+            ..FormatOptions::default()
+        };
+        assert_eq!(render_with(&function, &opts, 80), "f(alpha, beta)");
+        assert_eq!(render_with(&function, &opts, 10), "f(\n  alpha,\n  beta\n)");
+        let opts = FormatOptions {
+            tabs: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(render_with(&function, &opts, 10), "f(\n\talpha,\n\tbeta\n)");
+        let nested = NestWidth {
+            items: vec!["alpha".into(), "beta".into()],
+        };
+        assert_eq!(render(&nested, 80), "NEST WIDTH (alpha, beta)");
+        assert_eq!(
+            render(&nested, 10),
+            "NEST WIDTH\n  (\n    alpha,\n    beta\n  )"
+        );
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Aligned {
+        #[todoc(align)]
+        values: Vec<Ident>,
+    }
+
+    #[test]
+    fn align() {
+        let aligned = Aligned {
+            values: vec!["alpha".into(), "beta".into()],
+        };
+        assert_eq!(render(&aligned, 80), "VALUES alpha, beta");
+        assert_eq!(render(&aligned, 10), "VALUES alpha,\n       beta");
+        let opts = FormatOptions {
+            tabs: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            render_with(&aligned, &opts, 10),
+            "VALUES alpha,\n       beta"
+        );
+    }
+
+    #[test]
+    fn river() {
+        let opts = FormatOptions {
+            clause_layout: ClauseLayout::River,
+            ..FormatOptions::default()
+        };
+        let grouped = Select {
+            group_by: vec![ident("a"), ident("b")],
+            ..select()
+        };
+        assert_eq!(
+            render_with(&grouped, &opts, 80),
+            "SELECT a AS x WHERE b GROUP BY a, b"
+        );
+        assert_eq!(
+            render_with(&grouped, &opts, 16),
+            "  SELECT a AS x\n   WHERE b\nGROUP BY a, b"
+        );
+        let query = Query {
+            select: select(),
+            limit: Limit {
+                count: 10,
+                offset: 0,
+            },
+        };
+        assert_eq!(
+            render_with(&query, &opts, 80),
+            "SELECT a AS x WHERE b LIMIT 10"
+        );
+        assert_eq!(
+            render_with(&query, &opts, 16),
+            "SELECT a AS x\n WHERE b\nLIMIT 10"
+        );
+    }
+
+    #[test]
+    fn align_cell() {
+        let select = Select {
+            projection: vec![
+                SelectItem::Expr {
+                    expr: ident("a"),
+                    alias: Some("x".into()),
+                },
+                SelectItem::Expr {
+                    expr: ident("long"),
+                    alias: Some("y".into()),
+                },
+                SelectItem::Wildcard,
+            ],
+            selection: None,
+            group_by: Vec::new(),
+            having: None,
+        };
+        assert_eq!(render(&select, 80), "SELECT a AS x, long AS y, *");
+        assert_eq!(
+            render(&select, 16),
+            "SELECT\n    a    AS x,\n    long AS y,\n    *"
+        );
+    }
+
+    #[test]
+    fn to_doc_with() {
+        let select = Select {
+            selection: Some(Expr::Function {
+                name: "f".into(),
+                args: vec![ident("alpha"), ident("beta")],
+            }),
+            ..select()
+        };
+        let opts = FormatOptions::default();
+        assert_eq!(opts.render(&select.to_doc(), 80), render(&select, 80));
+        assert_eq!(
+            render(&select, 12),
+            "SELECT\n    a AS x\nWHERE f(\n          alpha,\n          beta\n      )"
+        );
+        let opts = FormatOptions {
+            list_style: ListStyle::Leading,
+            indent: 2,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            render_with(&select, &opts, 80),
+            "SELECT a AS x WHERE f(alpha, beta)"
+        );
+        assert_eq!(
+            render_with(&select, &opts, 12),
+            "SELECT\n  a AS x\nWHERE f(\n        alpha\n        , beta\n      )"
+        );
+    }
+
+    fn like() -> Expr {
+        Expr::Like {
+            expr: Box::new(ident("name")),
+            negated: true,
+            case_insensitive: true,
+            pattern: Box::new(Expr::Parameter(1)),
+            escape: None,
+        }
+    }
+
+    #[test]
+    fn keyword_case() {
+        assert_eq!(render(&like(), 80), "name NOT ILIKE $1");
+        let opts = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            ..FormatOptions::default()
+        };
+        assert_eq!(render_with(&like(), &opts, 80), "name not ilike $1");
+        assert_eq!(render_with(&like(), &opts, 10), "name\nnot\nilike\n$1");
+        let opts = FormatOptions {
+            keyword_case: KeywordCase::Capitalized,
+            ..FormatOptions::default()
+        };
+        assert_eq!(render_with(&like(), &opts, 80), "name Not Ilike $1");
+    }
+
+    #[test]
+    fn dialect() {
+        let opts = FormatOptions {
+            dialect: Dialect::Mysql,
+            ..FormatOptions::default()
+        };
+        assert_eq!(render_with(&like(), &opts, 80), "name NOT LIKE $1");
+        assert_eq!(render_with(&like(), &opts, 10), "name\nNOT\nLIKE\n$1");
+        let opts = FormatOptions {
+            dialect: Dialect::Postgres,
+            ..FormatOptions::default()
+        };
+        assert_eq!(render_with(&like(), &opts, 80), "name NOT ILIKE $1");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(rename_all = "snake")]
+    struct ShowColumns {
+        if_exists: bool,
+        #[todoc(rename = "from")]
+        table: Option<Ident>,
+    }
+
+    #[test]
+    fn rename_all() {
+        let show = ShowColumns {
+            if_exists: true,
+            table: Some("t".into()),
+        };
+        assert_eq!(render(&show, 80), "SHOW COLUMNS if_exists from t");
+        assert_eq!(
+            render(&show, 10),
+            "SHOW COLUMNS\n    if_exists\n    from\n        t"
+        );
+        let opts = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            render_with(&show, &opts, 80),
+            "show columns if_exists from t"
+        );
+        let opts = FormatOptions {
+            keyword_case: KeywordCase::Upper,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            render_with(&show, &opts, 80),
+            "SHOW COLUMNS if_exists FROM t"
+        );
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Missing {
+        #[todoc(else = "ALL")]
+        names: Vec<Ident>,
+        #[todoc(no_name, show_empty, prefix = "[", suffix = "]")]
+        options: std::collections::HashMap<usize, Ident>,
+    }
+
+    #[test]
+    fn missing_lists() {
+        let missing = Missing {
+            names: Vec::new(),
+            options: std::collections::HashMap::new(),
+        };
+        assert_eq!(render(&missing, 80), "ALL []");
+        let present = Missing {
+            names: vec!["a".into()],
+            options: (0..4).rev().map(|i| (i, "v".into())).collect(),
+        };
+        assert_eq!(render(&present, 80), "NAMES a [0 = v, 1 = v, 2 = v, 3 = v]");
+        assert_eq!(
+            render(&present, 12),
+            "NAMES\n    a\n[0 = v,\n1 = v,\n2 = v,\n3 = v]"
+        );
+    }
+
+    #[test]
+    fn skip_if() {
+        let limit = Limit {
+            count: 10,
+            offset: 20,
+        };
+        assert_eq!(render(&limit, 80), "LIMIT 10 OFFSET 20");
+        assert_eq!(render(&limit, 5), "LIMIT 10\nOFFSET 20");
+        let unlimited = Limit {
+            count: usize::MAX,
+            offset: 20,
+        };
+        assert_eq!(render(&unlimited, 80), "OFFSET 20");
+        let default = Limit {
+            count: 10,
+            offset: 0,
+        };
+        assert_eq!(render(&default, 80), "LIMIT 10");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Ordered {
+        #[todoc(order = 2)]
+        a: Ident,
+        b: Ident,
+        #[todoc(ignore)]
+        ignored: Ident,
+        c: Ident,
+        #[todoc(order = 0)]
+        d: Ident,
+    }
+
+    #[test]
+    fn order() {
+        let ordered = Ordered {
+            a: "a".into(),
+            b: "b".into(),
+            ignored: "ignored".into(),
+            c: "c".into(),
+            d: "d".into(),
+        };
+        assert_eq!(render(&ordered, 80), "d b a c");
+        assert_eq!(render(&ordered, 1), "d\nb\na\nc");
+    }
+
+    #[test]
+    fn flatten() {
+        let query = Query {
+            select: select(),
+            limit: Limit {
+                count: 10,
+                offset: 0,
+            },
+        };
+        assert_eq!(render(&query, 80), "SELECT a AS x WHERE b LIMIT 10");
+        assert_eq!(render(&query, 10), "SELECT\n    a AS x\nWHERE b\nLIMIT 10");
+    }
+
+    #[test]
+    fn transparent() {
+        let values = Values(vec![
+            Value(vec![ident("a"), ident("b")]),
+            Value(vec![ident("c")]),
+        ]);
+        assert_eq!(render(&values, 80), "VALUE (a, b), VALUE (c)");
+        assert_eq!(
+            render(&values, 10),
+            "VALUE\n    (\n        a,\n        b\n    ),\nVALUE (c)"
+        );
+    }
+
+    #[test]
+    fn item_prefix() {
+        let table = TableWithJoins {
+            relation: "t".into(),
+            joins: vec!["u".into(), "v".into()],
+        };
+        assert_eq!(render(&table, 80), "t JOIN u JOIN v");
+        assert_eq!(render(&table, 5), "t\nJOIN u\nJOIN v");
+    }
+
+    #[test]
+    fn keyword_separator() {
+        let union = UnionAll(vec![select(), select()]);
+        assert_eq!(
+            render(&union, 80),
+            "SELECT a AS x WHERE b UNION ALL SELECT a AS x WHERE b"
+        );
+        assert_eq!(
+            render(&union, 10),
+            "SELECT\n    a AS x\nWHERE b\nUNION ALL\nSELECT\n    a AS x\nWHERE b"
+        );
+    }
+
+    #[test]
+    fn container_number() {
+        let drop = DropRolesStatement {
+            if_exists: true,
+            names: vec!["a".into()],
+        };
+        assert_eq!(render(&drop, 80), "DROP ROLE IF EXISTS a");
+        let drop = DropRolesStatement {
+            if_exists: false,
+            names: vec!["a".into(), "b".into()],
+        };
+        assert_eq!(render(&drop, 80), "DROP ROLES a, b");
+        assert_eq!(render(&drop, 5), "DROP ROLES\n    a,\n    b");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(no_name)]
+    struct Grant {
+        #[todoc(singular = "ROLE", plural = "ROLES")]
+        roles: Vec<Ident>,
+    }
+
+    #[test]
+    fn field_number() {
+        let grant = Grant {
+            roles: vec!["a".into()],
+        };
+        assert_eq!(render(&grant, 80), "ROLE a");
+        let grant = Grant {
+            roles: vec!["a".into(), "b".into()],
+        };
+        assert_eq!(render(&grant, 80), "ROLES a, b");
+        assert_eq!(render(&grant, 5), "ROLES\n    a,\n    b");
+    }
+
+    #[test]
+    fn option_bool() {
+        let order = OrderByExpr {
+            expr: ident("a"),
+            asc: Some(false),
+            nulls_last: None,
+        };
+        assert_eq!(render(&order, 80), "a DESC");
+        let order = OrderByExpr {
+            expr: ident("a"),
+            asc: Some(true),
+            nulls_last: Some(true),
+        };
+        assert_eq!(render(&order, 80), "a ASC NULLS LAST");
+        assert_eq!(render(&order, 5), "a\nASC\nNULLS LAST");
+    }
+
+    #[derive(ToDoc)]
+    #[todoc(template = "DROP TABLE {name}")]
+    struct DropTable {
+        name: Ident,
+    }
+
+    #[test]
+    fn template() {
+        let create = CreateTableStatement {
+            temporary: false,
+            if_not_exists: true,
+            name: "t".into(),
+            columns: vec!["a".into(), "b".into()],
+        };
+        assert_eq!(render(&create, 80), "CREATE TABLE IF NOT EXISTS t (a, b)");
+        assert_eq!(
+            render(&create, 10),
+            "CREATE TABLE\n    IF NOT EXISTS\n    t\n    (a, b)"
+        );
+        let drop = DropTable { name: "t".into() };
+        assert_eq!(render(&drop, 80), "DROP TABLE t");
+        assert_eq!(render(&drop, 8), "DROP TABLE\n    t");
+    }
+
+    struct Case {
+        operand: Expr,
+        name: Ident,
+    }
+
+    impl ToDoc for Case {
+        fn to_doc_with(&self, opts: &FormatOptions) -> RcDoc<()> {
+            todoc!(opts, group("CASE" nest(line {self.operand}) line "END" space raw(self.name.as_str())))
+        }
+    }
+
+    #[test]
+    fn todoc() {
+        let case = Case {
+            operand: ident("a"),
+            name: "c".into(),
+        };
+        assert_eq!(render(&case, 80), "CASE a END c");
+        assert_eq!(render(&case, 8), "CASE\n    a\nEND c");
+    }
+
+    #[derive(ToDoc)]
+    enum Function {
+        #[todoc(call = "COALESCE")]
+        Coalesce(#[todoc(no_name)] Vec<Expr>),
+    }
+
+    #[test]
+    fn call() {
+        let cast = Expr::Cast {
+            expr: Box::new(ident("a")),
+            data_type: "int".into(),
+        };
+        assert_eq!(render(&cast, 80), "CAST(a AS int)");
+        assert_eq!(render(&cast, 5), "CAST(\n    a\n    AS int\n)");
+        let count = Expr::Count {
+            distinct: true,
+            args: vec![ident("a"), ident("b")],
+        };
+        assert_eq!(render(&count, 80), "COUNT(DISTINCT a, b)");
+        assert_eq!(render(&count, 5), "COUNT(\n    DISTINCT\n    a,\n    b\n)");
+        let coalesce = Function::Coalesce(vec![ident("a"), ident("b")]);
+        assert_eq!(render(&coalesce, 80), "COALESCE(a, b)");
+        assert_eq!(render(&coalesce, 5), "COALESCE(\n    a,\n    b\n)");
+    }
+}